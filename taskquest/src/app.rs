@@ -1,8 +1,17 @@
+use crate::clock::{Clock, DayBoundary};
+use crate::config::Config;
 use crate::db::Database;
-use crate::models::{Task, TaskStatus, UserStats};
-use crate::scoring;
-use anyhow::Result;
-use chrono::Utc;
+use crate::editor::TextEditor;
+use crate::models::{self, NewTask, Project, Recurrence, SearchHit, SubtaskProgress, Task, TaskEventKind, TaskRow, TaskStatus, UserStats};
+use crate::recompute::{self, Recompute};
+use crate::scoring::{self, ScoringStrategy};
+use crate::undo::{Snapshot, UndoEntry, UndoStack};
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum View {
@@ -16,16 +25,218 @@ pub enum InputMode {
     Normal,
     AddingTask,
     EditingTask,
+    ConfirmRecompute,
+    FilteringTags,
+    PickingProject,
+    NamingProject,
+    EditingNotes,
+    Searching,
+    FuzzyFiltering,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputFocus {
     Title,
     Difficulty,
+    Estimate,
+    Dependencies,
+    DueDate,
+    Recurrence,
 }
 
+/// What the task list can be ordered by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Created,
+    Due,
+    Difficulty,
+    Status,
+    Points,
+    Time,
+    Title,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 7] = [
+        SortKey::Created,
+        SortKey::Due,
+        SortKey::Difficulty,
+        SortKey::Status,
+        SortKey::Points,
+        SortKey::Time,
+        SortKey::Title,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortKey::Created => "created",
+            SortKey::Due => "due",
+            SortKey::Difficulty => "difficulty",
+            SortKey::Status => "status",
+            SortKey::Points => "points",
+            SortKey::Time => "time",
+            SortKey::Title => "title",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            SortKey::Created => "Newest first",
+            SortKey::Due => "Overdue first, then by due date; undated tasks last",
+            SortKey::Difficulty => "Hardest first",
+            SortKey::Status => "In progress, paused, pending, blocked, then completed",
+            SortKey::Points => "Most points first, including points earned by subtasks",
+            SortKey::Time => "Most tracked time first",
+            SortKey::Title => "Alphabetical",
+        }
+    }
+
+    /// Which end of the list comes first, in words
+    fn direction(&self, reverse: bool) -> &'static str {
+        let (natural, reversed) = match self {
+            SortKey::Created => ("newest first", "oldest first"),
+            SortKey::Due => ("soonest first", "latest first"),
+            SortKey::Difficulty => ("hardest first", "easiest first"),
+            SortKey::Status => ("active first", "completed first"),
+            SortKey::Points => ("most first", "fewest first"),
+            SortKey::Time => ("longest first", "shortest first"),
+            SortKey::Title => ("A-Z", "Z-A"),
+        };
+        if reverse { reversed } else { natural }
+    }
+
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|k| k == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == name)
+    }
+
+    /// Order of two tasks in this key's natural direction
+    fn compare(&self, a: &Task, b: &Task, ctx: &SortContext) -> Ordering {
+        match self {
+            SortKey::Created => (b.created_at, b.id).cmp(&(a.created_at, a.id)),
+            SortKey::Due => {
+                let key = |t: &Task| (t.status == TaskStatus::Completed, t.due_date.is_none(), t.due_date);
+                key(a).cmp(&key(b))
+            }
+            SortKey::Difficulty => b.difficulty.cmp(&a.difficulty),
+            SortKey::Status => {
+                let rank = |t: &Task| match t.status {
+                    TaskStatus::InProgress => 0,
+                    TaskStatus::Paused => 1,
+                    TaskStatus::Pending if t.is_blocked() => 3,
+                    TaskStatus::Pending => 2,
+                    TaskStatus::Completed => 4,
+                };
+                rank(a).cmp(&rank(b))
+            }
+            SortKey::Points => ctx.points(b).cmp(&ctx.points(a)),
+            SortKey::Time => b.active_seconds_at(ctx.now).cmp(&a.active_seconds_at(ctx.now)),
+            SortKey::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+        }
+    }
+}
+
+/// What sorting needs beyond the tasks themselves
+pub struct SortContext<'a> {
+    pub now: DateTime<Utc>,
+    pub subtask_progress: &'a HashMap<i64, SubtaskProgress>,
+}
+
+impl SortContext<'_> {
+    fn points(&self, task: &Task) -> i64 {
+        task.points_earned as i64 + self.subtask_progress.get(&task.id).map_or(0, |p| p.points)
+    }
+}
+
+/// Order of the task list: a key, optionally reversed, and a second key
+/// (always in its natural direction) for ties. Subtasks stay under their
+/// parent, so this orders top-level tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortMode {
+    pub key: SortKey,
+    pub reverse: bool,
+    pub then: Option<SortKey>,
+}
+
+impl Default for SortMode {
+    fn default() -> Self {
+        Self {
+            key: SortKey::Created,
+            reverse: false,
+            then: None,
+        }
+    }
+}
+
+impl SortMode {
+    pub fn label(&self) -> String {
+        let mut label = format!("{}, {}", self.key.as_str(), self.key.direction(self.reverse));
+        if let Some(then) = self.then {
+            label.push_str(&format!(", then {}", then.as_str()));
+        }
+        label
+    }
+
+    /// Reorder tasks; ties keep their order, which is newest first as loaded
+    pub fn apply(&self, tasks: &mut [Task], ctx: &SortContext) {
+        tasks.sort_by(|a, b| {
+            let primary = self.key.compare(a, b, ctx);
+            let primary = if self.reverse { primary.reverse() } else { primary };
+            primary.then_with(|| self.then.map_or(Ordering::Equal, |then| then.compare(a, b, ctx)))
+        });
+    }
+}
+
+/// Stored in the settings table as `[-]key[,then]`, e.g. `-points,title`
+impl fmt::Display for SortMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", if self.reverse { "-" } else { "" }, self.key.as_str())?;
+        if let Some(then) = self.then {
+            write!(f, ",{}", then.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SortMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid sort order '{}'", s);
+        let (primary, then) = match s.split_once(',') {
+            Some((primary, then)) => (primary, Some(SortKey::parse(then).ok_or_else(invalid)?)),
+            None => (s, None),
+        };
+        let (reverse, key) = match primary.strip_prefix('-') {
+            Some(key) => (true, key),
+            None => (false, primary),
+        };
+        Ok(Self {
+            key: SortKey::parse(key).ok_or_else(invalid)?,
+            reverse,
+            then,
+        })
+    }
+}
+
+const STRATEGY_SETTING: &str = "scoring_strategy";
+const SORT_SETTING: &str = "task_sort";
+
+/// Most results the search popup lists
+const SEARCH_LIMIT: usize = 50;
+
 pub struct App {
     pub db: Database,
+    pub clock: Box<dyn Clock>,
+    pub day_boundary: DayBoundary,
+    pub config: Config,
+    pub strategy: Box<dyn ScoringStrategy>,
+    pub previous_strategy: Option<String>, // set when the strategy changed since last run
+    pub pending_recompute: Option<Recompute>, // preview awaiting confirmation
     pub tasks: Vec<Task>,
     pub user_stats: UserStats,
     pub current_view: View,
@@ -35,19 +246,74 @@ pub struct App {
     pub show_completed: bool,
     pub input_buffer: String,
     pub difficulty_input: String,
+    pub estimate_input: String, // as typed: "30m", "1h30m"
+    pub dependencies_input: String, // prerequisite ids, e.g. "#3, #7"
+    pub due_input: String,          // as typed: "fri", "+3d", "2026-10-31"
+    pub recurrence_input: String,   // as typed: "daily", "weekly mon,thu"
+    pub sort_mode: SortMode,
+    pub notes_editor: TextEditor,
     pub should_quit: bool,
     pub status_message: Option<String>,
     pub editing_task_id: Option<i64>,
+    pub undo_stack: UndoStack,
+    pub tag_filter: BTreeSet<String>, // empty = show every task
+    pub available_tags: Vec<String>,  // choices in the tag filter popup
+    pub tag_cursor: usize,
+    pub projects: Vec<Project>,
+    pub current_project: Option<Project>, // None = all projects
+    pub project_cursor: usize, // 0 = all, then projects, then "new project"
+    pub collapsed: HashSet<i64>, // tasks whose subtasks are hidden
+    pub subtask_progress: HashMap<i64, SubtaskProgress>,
+    pub adding_parent_id: Option<i64>, // parent of the subtask being added
+    pub search_input: String,
+    pub search_results: Vec<SearchHit>,
+    pub search_cursor: usize,
+    pub fuzzy_filter: String, // empty = no fuzzy filtering
 }
 
 impl App {
-    pub fn new() -> Result<Self> {
-        let db = Database::new()?;
-        let tasks = db.get_all_tasks()?;
+    pub fn new(clock: Box<dyn Clock>) -> Result<Self> {
+        let db = Database::new(clock.now())?;
+        Self::open(Config::load()?, db, clock)
+    }
+
+    /// Build the app around an already opened database
+    fn open(config: Config, mut db: Database, clock: Box<dyn Clock>) -> Result<Self> {
+        let day_boundary = config.day.boundary()?;
+        let strategy =
+            scoring::build_strategy(config.active_profile()?.strategy, config.scoring.clone(), day_boundary);
+        // An unreadable saved order falls back to newest first
+        let sort_mode: SortMode = db
+            .get_setting(SORT_SETTING)?
+            .and_then(|saved| saved.parse().ok())
+            .unwrap_or_default();
+        let mut tasks = db.get_all_tasks()?;
+        let subtask_progress = db.get_subtask_progress()?;
+        let ctx = SortContext {
+            now: clock.now(),
+            subtask_progress: &subtask_progress,
+        };
+        sort_mode.apply(&mut tasks, &ctx);
         let user_stats = db.get_user_stats()?;
 
-        Ok(Self {
+        // Offer to rescore history when the strategy differs from last run
+        let previous_strategy = match db.get_setting(STRATEGY_SETTING)? {
+            Some(previous) if previous != strategy.kind().as_str() => Some(previous),
+            Some(_) => None,
+            None => {
+                db.set_setting(STRATEGY_SETTING, strategy.kind().as_str())?;
+                None
+            }
+        };
+
+        let mut app = Self {
             db,
+            clock,
+            day_boundary,
+            config,
+            strategy,
+            previous_strategy,
+            pending_recompute: None,
             tasks,
             user_stats,
             current_view: View::TaskList,
@@ -57,10 +323,36 @@ impl App {
             show_completed: false,
             input_buffer: String::new(),
             difficulty_input: String::new(),
+            estimate_input: String::new(),
+            dependencies_input: String::new(),
+            due_input: String::new(),
+            recurrence_input: String::new(),
+            sort_mode,
+            notes_editor: TextEditor::default(),
             should_quit: false,
             status_message: None,
             editing_task_id: None,
-        })
+            undo_stack: UndoStack::default(),
+            tag_filter: BTreeSet::new(),
+            available_tags: Vec::new(),
+            tag_cursor: 0,
+            projects: Vec::new(),
+            current_project: None,
+            project_cursor: 0,
+            collapsed: HashSet::new(),
+            subtask_progress,
+            adding_parent_id: None,
+            search_input: String::new(),
+            search_results: Vec::new(),
+            search_cursor: 0,
+            fuzzy_filter: String::new(),
+        };
+
+        if app.previous_strategy.is_some() {
+            app.preview_recompute()?;
+        }
+
+        Ok(app)
     }
 
     pub fn refresh_tasks(&mut self) -> Result<()> {
@@ -69,6 +361,12 @@ impl App {
         } else {
             self.db.get_pending_tasks()?
         };
+        self.subtask_progress = self.db.get_subtask_progress()?;
+        let ctx = SortContext {
+            now: self.clock.now(),
+            subtask_progress: &self.subtask_progress,
+        };
+        self.sort_mode.apply(&mut self.tasks, &ctx);
         Ok(())
     }
 
@@ -77,8 +375,123 @@ impl App {
         Ok(())
     }
 
+    /// Filtered tasks in tree order, as shown in the task list. With a
+    /// fuzzy filter, only matching titles are kept, best match first, and
+    /// collapsed parents are opened so no match is hidden.
+    pub fn visible_rows(&self) -> Vec<TaskRow<'_>> {
+        let mut tasks: Vec<&Task> = self
+            .tasks
+            .iter()
+            .filter(|task| self.tag_filter.is_empty() || task.tags.iter().any(|t| self.tag_filter.contains(t)))
+            .filter(|task| self.project_scope().is_none_or(|id| task.project_id == Some(id)))
+            .collect();
+        if self.fuzzy_filter.is_empty() {
+            return models::build_tree(&tasks, &self.collapsed);
+        }
+
+        let mut scored: Vec<(i64, &Task)> = tasks
+            .into_iter()
+            .filter_map(|task| models::fuzzy_match(&self.fuzzy_filter, &task.title).map(|m| (m.score, task)))
+            .collect();
+        // Stable, so equal scores keep the list order
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        tasks = scored.into_iter().map(|(_, task)| task).collect();
+        models::build_tree(&tasks, &HashSet::new())
+    }
+
     pub fn visible_tasks(&self) -> Vec<&Task> {
-        self.tasks.iter().collect()
+        self.visible_rows().into_iter().map(|row| row.task).collect()
+    }
+
+    /// Points of a task plus everything its subtasks earned
+    pub fn rolled_up_points(&self, task: &Task) -> i64 {
+        let below = self.subtask_progress.get(&task.id).map_or(0, |p| p.points);
+        task.points_earned as i64 + below
+    }
+
+    /// Hide the selected task's subtasks, or jump to its parent when it
+    /// has none showing
+    pub fn collapse_selected(&mut self) {
+        let Some(task) = self.selected_task() else {
+            return;
+        };
+        let (id, parent_id) = (task.id, task.parent_id);
+
+        let rows = self.visible_rows();
+        let has_children = rows.iter().any(|row| row.task.id == id && row.has_children);
+        if has_children && !self.collapsed.contains(&id) {
+            self.collapsed.insert(id);
+        } else if let Some(index) = parent_id.and_then(|pid| rows.iter().position(|row| row.task.id == pid)) {
+            self.selected_task_index = index;
+        }
+    }
+
+    pub fn expand_selected(&mut self) {
+        if let Some(id) = self.selected_task().map(|t| t.id) {
+            self.collapsed.remove(&id);
+        }
+    }
+
+    /// Project the task list and Stats view are limited to, if any
+    pub fn project_scope(&self) -> Option<i64> {
+        self.current_project.as_ref().map(|p| p.id)
+    }
+
+    /// The user's current day, for due dates
+    pub fn today(&self) -> NaiveDate {
+        self.day_boundary.day_of(self.clock.now())
+    }
+
+    pub fn sort_context(&self) -> SortContext<'_> {
+        SortContext {
+            now: self.clock.now(),
+            subtask_progress: &self.subtask_progress,
+        }
+    }
+
+    /// Sort by the next key, in its natural direction
+    pub fn cycle_sort_key(&mut self) -> Result<()> {
+        let key = self.sort_mode.key.next();
+        self.set_sort_mode(SortMode {
+            key,
+            reverse: false,
+            then: self.sort_mode.then.filter(|then| *then != key),
+        })
+    }
+
+    pub fn reverse_sort(&mut self) -> Result<()> {
+        self.set_sort_mode(SortMode {
+            reverse: !self.sort_mode.reverse,
+            ..self.sort_mode
+        })
+    }
+
+    /// Cycle the tie-breaking key through every other key and back to none
+    pub fn cycle_secondary_sort(&mut self) -> Result<()> {
+        let choices: Vec<Option<SortKey>> = std::iter::once(None)
+            .chain(SortKey::ALL.into_iter().filter(|k| *k != self.sort_mode.key).map(Some))
+            .collect();
+        let index = choices.iter().position(|c| *c == self.sort_mode.then).unwrap_or(0);
+        let then = choices[(index + 1) % choices.len()];
+        self.set_sort_mode(SortMode { then, ..self.sort_mode })
+    }
+
+    /// Re-sort the list and remember the order for the next session
+    pub fn set_sort_mode(&mut self, sort_mode: SortMode) -> Result<()> {
+        self.sort_mode = sort_mode;
+        self.db.set_setting(SORT_SETTING, &sort_mode.to_string())?;
+        self.refresh_tasks()?;
+        self.selected_task_index = 0;
+        self.status_message = Some(format!("Sorted by {}", sort_mode.label()));
+        Ok(())
+    }
+
+    /// Keep the selection on a visible task after the list shrinks
+    fn clamp_selection(&mut self) {
+        let visible = self.visible_tasks().len();
+        if self.selected_task_index >= visible {
+            self.selected_task_index = visible.saturating_sub(1);
+        }
     }
 
     pub fn selected_task(&self) -> Option<&Task> {
@@ -103,35 +516,137 @@ impl App {
         self.input_focus = InputFocus::Title;
         self.input_buffer.clear();
         self.difficulty_input = String::from("5");
+        self.estimate_input.clear();
+        self.dependencies_input.clear();
+        self.due_input.clear();
+        self.recurrence_input.clear();
+    }
+
+    /// Add a task under the selected one
+    pub fn start_add_subtask(&mut self) {
+        let Some(parent) = self.selected_task() else {
+            return;
+        };
+        if parent.status == TaskStatus::Completed {
+            self.status_message = Some("Cannot add subtasks to a completed task".to_string());
+            return;
+        }
+
+        let parent_id = parent.id;
+        self.start_add_task();
+        self.adding_parent_id = Some(parent_id);
+        self.collapsed.remove(&parent_id);
     }
 
     pub fn start_edit_task(&mut self) {
-        if self.selected_task_index < self.tasks.len() {
-            let task = &self.tasks[self.selected_task_index];
-            let title = task.title.clone();
+        if let Some(task) = self.selected_task() {
+            let title = task.title_with_tags();
             let difficulty = task.difficulty;
+            let estimate = task.estimate_minutes.map(models::format_minutes).unwrap_or_default();
+            let dependencies = models::format_task_ids(&task.depends_on);
+            let due = task.due_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default();
+            let recurrence = task.recurrence.as_ref().map(|r| r.to_string()).unwrap_or_default();
             let id = task.id;
 
             self.input_mode = InputMode::EditingTask;
             self.input_focus = InputFocus::Title;
             self.input_buffer = title;
             self.difficulty_input = difficulty.to_string();
+            self.estimate_input = estimate;
+            self.dependencies_input = dependencies;
+            self.due_input = due;
+            self.recurrence_input = recurrence;
             self.editing_task_id = Some(id);
         }
     }
 
+    /// Open the notes editor on the selected task
+    pub fn start_edit_notes(&mut self) {
+        if let Some((id, notes)) = self.selected_task().map(|t| (t.id, t.notes.clone())) {
+            self.notes_editor = TextEditor::with_text(&notes);
+            self.editing_task_id = Some(id);
+            self.input_mode = InputMode::EditingNotes;
+        }
+    }
+
+    pub fn save_notes(&mut self) -> Result<()> {
+        if let Some(task_id) = self.editing_task_id {
+            let notes = self.notes_editor.text();
+            let task = self.set_notes(task_id, notes)?;
+            self.status_message = Some(format!("Notes for '{}' saved", task.title));
+        }
+        self.cancel_input();
+        Ok(())
+    }
+
+    /// Replace the notes of a task; surrounding blank lines are dropped
+    pub fn set_notes(&mut self, task_id: i64, notes: String) -> Result<Task> {
+        let mut task = self.find_task(task_id)?;
+        let notes = notes.trim_matches('\n').trim_end().to_string();
+        if task.notes == notes {
+            return Ok(task);
+        }
+        let before = self.snapshot(task_id)?;
+
+        let change = match (task.notes.is_empty(), notes.is_empty()) {
+            (true, _) => "notes added",
+            (false, true) => "notes cleared",
+            (false, false) => "notes changed",
+        };
+        task.notes = notes;
+        self.db.update_task(&task)?;
+        self.db.record_event(task_id, TaskEventKind::Edited, self.clock.now(), Some(change.to_string()))?;
+        self.record_undo(format!("edit '{}'", task.title), task_id, before)?;
+        self.refresh_tasks()?;
+        Ok(task)
+    }
+
+    /// Bracketed paste: multiline into the notes editor, the first line
+    /// into single-line fields
+    pub fn paste(&mut self, text: &str) {
+        match self.input_mode {
+            InputMode::EditingNotes => self.notes_editor.insert_str(text),
+            InputMode::AddingTask | InputMode::EditingTask => {
+                for c in text.lines().next().unwrap_or_default().chars() {
+                    self.handle_input_char(c);
+                }
+            }
+            InputMode::NamingProject => {
+                self.input_buffer.push_str(text.lines().next().unwrap_or_default());
+            }
+            InputMode::Searching => {
+                self.search_input.push_str(text.lines().next().unwrap_or_default());
+                self.update_search();
+            }
+            InputMode::FuzzyFiltering => {
+                let filter = format!("{}{}", self.fuzzy_filter, text.lines().next().unwrap_or_default());
+                self.set_fuzzy_filter(filter);
+            }
+            _ => {}
+        }
+    }
+
     pub fn cancel_input(&mut self) {
         self.input_mode = InputMode::Normal;
         self.input_focus = InputFocus::Title;
         self.input_buffer.clear();
         self.difficulty_input.clear();
+        self.estimate_input.clear();
+        self.dependencies_input.clear();
+        self.due_input.clear();
+        self.recurrence_input.clear();
         self.editing_task_id = None;
+        self.adding_parent_id = None;
     }
 
     pub fn toggle_input_focus(&mut self) {
         self.input_focus = match self.input_focus {
             InputFocus::Title => InputFocus::Difficulty,
-            InputFocus::Difficulty => InputFocus::Title,
+            InputFocus::Difficulty => InputFocus::Estimate,
+            InputFocus::Estimate => InputFocus::Dependencies,
+            InputFocus::Dependencies => InputFocus::DueDate,
+            InputFocus::DueDate => InputFocus::Recurrence,
+            InputFocus::Recurrence => InputFocus::Title,
         };
     }
 
@@ -143,6 +658,26 @@ impl App {
                     self.difficulty_input.push(c);
                 }
             }
+            InputFocus::Estimate => {
+                if c.is_ascii_digit() || matches!(c, 'h' | 'm' | '.' | ' ') {
+                    self.estimate_input.push(c);
+                }
+            }
+            InputFocus::Dependencies => {
+                if c.is_ascii_digit() || matches!(c, ',' | ' ' | '#') {
+                    self.dependencies_input.push(c);
+                }
+            }
+            InputFocus::DueDate => {
+                if c.is_ascii_alphanumeric() || matches!(c, '+' | '-') {
+                    self.due_input.push(c);
+                }
+            }
+            InputFocus::Recurrence => {
+                if c.is_ascii_alphanumeric() || matches!(c, ' ' | ',' | ':') {
+                    self.recurrence_input.push(c);
+                }
+            }
         }
     }
 
@@ -154,31 +689,138 @@ impl App {
             InputFocus::Difficulty => {
                 self.difficulty_input.pop();
             }
+            InputFocus::Estimate => {
+                self.estimate_input.pop();
+            }
+            InputFocus::Dependencies => {
+                self.dependencies_input.pop();
+            }
+            InputFocus::DueDate => {
+                self.due_input.pop();
+            }
+            InputFocus::Recurrence => {
+                self.recurrence_input.pop();
+            }
         }
     }
 
     pub fn submit_task(&mut self) -> Result<()> {
-        let title = self.input_buffer.trim().to_string();
+        let (title, tags) = models::split_tags(&self.input_buffer);
         if title.is_empty() {
             self.status_message = Some("Task title cannot be empty".to_string());
             return Ok(());
         }
 
         let difficulty = self.difficulty_input.parse::<i32>().unwrap_or(5).clamp(1, 10);
+        let estimate_minutes = match models::parse_estimate(&self.estimate_input) {
+            Ok(minutes) => minutes,
+            Err(message) => {
+                self.status_message = Some(message);
+                return Ok(());
+            }
+        };
+
+        // Bad dependencies keep the popup open so they can be corrected
+        let depends_on = match models::parse_task_ids(&self.dependencies_input) {
+            Ok(ids) => ids,
+            Err(message) => {
+                self.status_message = Some(message);
+                return Ok(());
+            }
+        };
+        if let Err(err) = self.check_dependencies(self.editing_task_id, &depends_on) {
+            self.status_message = Some(err.to_string());
+            return Ok(());
+        }
+        let due_date = match models::parse_due_date(&self.due_input, self.today()) {
+            Ok(date) => date,
+            Err(message) => {
+                self.status_message = Some(message);
+                return Ok(());
+            }
+        };
+        let recurrence = match models::parse_recurrence(&self.recurrence_input, due_date.unwrap_or(self.today())) {
+            Ok(rule) => rule,
+            Err(message) => {
+                self.status_message = Some(message);
+                return Ok(());
+            }
+        };
+        let is_subtask = match self.editing_task_id {
+            Some(id) => self.tasks.iter().any(|t| t.id == id && t.parent_id.is_some()),
+            None => self.adding_parent_id.is_some(),
+        };
+        if recurrence.is_some() && is_subtask {
+            self.status_message = Some("Subtasks cannot repeat".to_string());
+            return Ok(());
+        }
 
         match self.input_mode {
             InputMode::AddingTask => {
-                let task = self.db.create_task(title, difficulty)?;
-                self.status_message = Some(format!("Task '{}' added!", task.title));
-                self.refresh_tasks()?;
+                let mut new = NewTask::new(title, difficulty);
+                new.tags = tags;
+                new.project = self.current_project.clone();
+                new.parent_id = self.adding_parent_id;
+                new.depends_on = depends_on;
+                new.due_date = due_date;
+                new.recurrence = recurrence;
+                new.estimate_minutes = estimate_minutes;
+                self.add_task(new)?;
             }
             InputMode::EditingTask => {
                 if let Some(task_id) = self.editing_task_id {
-                    if let Some(task) = self.tasks.iter_mut().find(|t| t.id == task_id) {
+                    let before = self.snapshot(task_id)?;
+                    if let Some(mut task) = self.tasks.iter().find(|t| t.id == task_id).cloned() {
+                        let mut changes = Vec::new();
+                        if task.title != title {
+                            changes.push(format!("title '{}' → '{}'", task.title, title));
+                        }
+                        if task.difficulty != difficulty {
+                            changes.push(format!("difficulty {} → {}", task.difficulty, difficulty));
+                        }
+                        if task.estimate_minutes != estimate_minutes {
+                            changes.push(format!(
+                                "estimate {} → {}",
+                                format_estimate(task.estimate_minutes),
+                                format_estimate(estimate_minutes)
+                            ));
+                        }
+                        if task.tags != tags {
+                            changes.push(format!("tags [{}] → [{}]", task.tags.join(", "), tags.join(", ")));
+                        }
+                        if task.depends_on != depends_on {
+                            changes.push(format!(
+                                "depends on [{}] → [{}]",
+                                models::format_task_ids(&task.depends_on),
+                                models::format_task_ids(&depends_on)
+                            ));
+                        }
+                        if task.due_date != due_date {
+                            changes.push(format!("due {} → {}", format_due(task.due_date), format_due(due_date)));
+                        }
+
                         task.title = title.clone();
                         task.difficulty = difficulty;
-                        self.db.update_task(task)?;
+                        task.estimate_minutes = estimate_minutes;
+                        task.tags = tags;
+                        task.due_date = due_date;
+                        if let Some(change) = self.apply_recurrence(&mut task, recurrence)? {
+                            changes.push(change);
+                        }
+                        self.db.update_task(&task)?;
+                        self.db.set_task_tags(task_id, &task.tags)?;
+                        self.db.set_task_dependencies(task_id, &depends_on)?;
+                        if !changes.is_empty() {
+                            self.db.record_event(
+                                task_id,
+                                TaskEventKind::Edited,
+                                self.clock.now(),
+                                Some(changes.join(", ")),
+                            )?;
+                        }
+                        self.record_undo(format!("edit '{}'", title), task_id, before)?;
                         self.status_message = Some(format!("Task '{}' updated!", title));
+                        self.refresh_tasks()?;
                     }
                 }
             }
@@ -189,118 +831,683 @@ impl App {
         Ok(())
     }
 
-    pub fn delete_selected_task(&mut self) -> Result<()> {
-        if let Some(task) = self.selected_task() {
-            let task_id = task.id;
-            let task_title = task.title.clone();
-
-            self.db.delete_task(task_id)?;
-            self.status_message = Some(format!("Task '{}' deleted", task_title));
-            self.refresh_tasks()?;
+    /// Make sure every prerequisite exists and that `task_id` (None for a
+    /// task not created yet) would not end up depending on itself
+    fn check_dependencies(&self, task_id: Option<i64>, depends_on: &[i64]) -> Result<()> {
+        for id in depends_on {
+            self.find_task(*id)?;
+        }
+        let Some(task_id) = task_id else {
+            return Ok(());
+        };
+        if depends_on.contains(&task_id) {
+            bail!("Task #{} cannot depend on itself", task_id);
+        }
 
-            // Adjust selection
-            if self.selected_task_index >= self.tasks.len() && !self.tasks.is_empty() {
-                self.selected_task_index = self.tasks.len() - 1;
+        let graph = self.db.get_dependency_graph()?;
+        for id in depends_on {
+            if let Some(path) = models::dependency_path(&graph, *id, task_id) {
+                let chain: Vec<String> = std::iter::once(task_id)
+                    .chain(path)
+                    .map(|id| format!("#{}", id))
+                    .collect();
+                bail!(
+                    "Task #{} cannot depend on #{}: that would form a cycle {}",
+                    task_id,
+                    id,
+                    chain.join(" → ")
+                );
             }
         }
         Ok(())
     }
 
-    pub fn toggle_task(&mut self) -> Result<()> {
-        if self.selected_task_index >= self.tasks.len() {
-            return Ok(());
+    /// Replace the prerequisites of a task
+    pub fn set_dependencies(&mut self, task_id: i64, depends_on: Vec<i64>) -> Result<Task> {
+        let task = self.find_task(task_id)?;
+        self.check_dependencies(Some(task_id), &depends_on)?;
+        let before = self.snapshot(task_id)?;
+
+        self.db.set_task_dependencies(task_id, &depends_on)?;
+        self.db.record_event(
+            task_id,
+            TaskEventKind::Edited,
+            self.clock.now(),
+            Some(format!(
+                "depends on [{}] → [{}]",
+                models::format_task_ids(&task.depends_on),
+                models::format_task_ids(&depends_on)
+            )),
+        )?;
+        self.record_undo(format!("edit '{}'", task.title), task_id, before)?;
+        self.refresh_tasks()?;
+        self.find_task(task_id)
+    }
+
+    /// Set or clear the time estimate of a task
+    pub fn set_estimate(&mut self, task_id: i64, estimate_minutes: Option<i64>) -> Result<Task> {
+        let mut task = self.find_task(task_id)?;
+        let before = self.snapshot(task_id)?;
+
+        let change = format!(
+            "estimate {} → {}",
+            format_estimate(task.estimate_minutes),
+            format_estimate(estimate_minutes)
+        );
+        task.estimate_minutes = estimate_minutes;
+        self.db.update_task(&task)?;
+        self.db.record_event(task_id, TaskEventKind::Edited, self.clock.now(), Some(change))?;
+        self.record_undo(format!("edit '{}'", task.title), task_id, before)?;
+        self.refresh_tasks()?;
+        Ok(task)
+    }
+
+    /// The difficulty whose completed tasks took, on average, closest to
+    /// `estimate_minutes`; None until some tasks have tracked time
+    pub fn suggested_difficulty(&self, estimate_minutes: i64) -> Result<Option<i32>> {
+        let avg_times = self.db.get_avg_time_by_difficulty(None)?;
+        Ok(models::suggest_difficulty(&avg_times, estimate_minutes.saturating_mul(60) as f64))
+    }
+
+    /// Set or clear the due date of a task
+    pub fn set_due_date(&mut self, task_id: i64, due_date: Option<NaiveDate>) -> Result<Task> {
+        let mut task = self.find_task(task_id)?;
+        let before = self.snapshot(task_id)?;
+
+        let change = format!("due {} → {}", format_due(task.due_date), format_due(due_date));
+        task.due_date = due_date;
+        self.db.update_task(&task)?;
+        self.db.record_event(task_id, TaskEventKind::Edited, self.clock.now(), Some(change))?;
+        self.record_undo(format!("edit '{}'", task.title), task_id, before)?;
+        self.refresh_tasks()?;
+        Ok(task)
+    }
+
+    /// Start, change or stop the series a task repeats in. Returns the
+    /// change for the task's history, if there was one.
+    fn apply_recurrence(&mut self, task: &mut Task, recurrence: Option<Recurrence>) -> Result<Option<String>> {
+        if task.recurrence == recurrence {
+            return Ok(None);
+        }
+        if recurrence.is_some() && task.parent_id.is_some() {
+            bail!("Task #{} is a subtask; subtasks cannot repeat", task.id);
         }
 
-        let task = &mut self.tasks[self.selected_task_index];
-        let status_msg = match task.status {
-            TaskStatus::Pending => {
-                task.status = TaskStatus::InProgress;
-                task.started_at = Some(Utc::now());
-                "Task started!"
-            }
-            TaskStatus::InProgress => {
-                task.status = TaskStatus::Paused;
-                task.paused_at = Some(Utc::now());
-                "Task paused"
-            }
-            TaskStatus::Paused => {
-                if let Some(paused_at) = task.paused_at {
-                    let pause_duration = (Utc::now() - paused_at).num_seconds();
-                    task.total_pause_duration += pause_duration;
+        match (task.series_id, &recurrence) {
+            (Some(series_id), rule) => self.db.set_series_rule(series_id, rule.as_ref())?,
+            (None, Some(rule)) => {
+                task.series_id = Some(self.db.start_series(rule, self.clock.now())?);
+                if task.due_date.is_none() {
+                    task.due_date = rule.first_on_or_after(self.today());
                 }
-                task.status = TaskStatus::InProgress;
-                task.paused_at = None;
-                "Task resumed!"
             }
-            TaskStatus::Completed => {
-                return Ok(());
+            (None, None) => {}
+        }
+
+        let describe = |rule: &Option<Recurrence>| rule.as_ref().map_or_else(|| "never".to_string(), |r| r.to_string());
+        let change = format!("repeats {} → {}", describe(&task.recurrence), describe(&recurrence));
+        task.recurrence = recurrence;
+        Ok(Some(change))
+    }
+
+    /// Start, change or stop repeating a task
+    pub fn set_recurrence(&mut self, task_id: i64, recurrence: Option<Recurrence>) -> Result<Task> {
+        let mut task = self.find_task(task_id)?;
+        let before = self.snapshot(task_id)?;
+
+        let Some(change) = self.apply_recurrence(&mut task, recurrence)? else {
+            return Ok(task);
+        };
+        self.db.update_task(&task)?;
+        self.db.record_event(task_id, TaskEventKind::Edited, self.clock.now(), Some(change))?;
+        self.record_undo(format!("edit '{}'", task.title), task_id, before)?;
+        self.refresh_tasks()?;
+        Ok(task)
+    }
+
+    /// Due date of the instance that follows `done` in its series: the first
+    /// occurrence after both its due date and the day it was done
+    fn next_due_date(&self, done: &Task, now: DateTime<Utc>) -> Result<Option<NaiveDate>> {
+        let (Some(rule), Some(_)) = (&done.recurrence, done.series_id) else {
+            return Ok(None);
+        };
+        let today = self.day_boundary.day_of(now);
+        let after = done.due_date.map_or(today, |due| due.max(today));
+        let next = rule
+            .next_after(after)
+            .ok_or_else(|| anyhow!("Task #{} repeats {}, which runs past the last supported date", done.id, rule))?;
+        Ok(Some(next))
+    }
+
+    /// Create the next instance of a completed recurring task, due on `due`
+    fn spawn_next_instance(&mut self, done: &Task, due: Option<NaiveDate>, now: DateTime<Utc>) -> Result<Option<Task>> {
+        let (Some(rule), Some(series_id), Some(due)) = (&done.recurrence, done.series_id, due) else {
+            return Ok(None);
+        };
+
+        let mut new = NewTask::new(done.title.clone(), done.difficulty);
+        new.tags = done.tags.clone();
+        new.estimate_minutes = done.estimate_minutes;
+        new.project = match done.project_id {
+            Some(id) => self.db.get_project(id)?,
+            None => None,
+        };
+        new.due_date = Some(due);
+        new.series_id = Some(series_id);
+
+        let before = Snapshot {
+            task: None,
+            ledger: Vec::new(),
+            user_stats: self.db.get_user_stats()?,
+        };
+        let next = self.db.create_task(&new, now)?;
+        self.db.record_event(
+            next.id,
+            TaskEventKind::Created,
+            now,
+            Some(format!("difficulty {}, repeats {} after #{}", next.difficulty, rule, done.id)),
+        )?;
+        let entry = UndoEntry {
+            description: format!("add '{}'", next.title),
+            task_id: next.id,
+            before,
+            after: self.snapshot(next.id)?,
+            linked: Vec::new(),
+        };
+        self.undo_stack.link_to_last(entry);
+        Ok(Some(next))
+    }
+
+    pub fn add_task(&mut self, mut new: NewTask) -> Result<Task> {
+        self.check_dependencies(None, &new.depends_on)?;
+        if let Some(rule) = &new.recurrence {
+            if new.parent_id.is_some() {
+                bail!("Subtasks cannot repeat");
             }
+            if new.due_date.is_none() {
+                new.due_date = rule.first_on_or_after(self.today());
+            }
+        }
+
+        // Subtasks live in their parent's project
+        if let Some(parent_id) = new.parent_id {
+            let parent = self.find_task(parent_id)?;
+            if parent.status == TaskStatus::Completed {
+                bail!("Task #{} is already completed; cannot add subtasks", parent_id);
+            }
+            if let Some(name) = parent.project {
+                new.project = self.db.find_project(&name)?;
+            }
+        }
+
+        let before = Snapshot {
+            task: None,
+            ledger: Vec::new(),
+            user_stats: self.user_stats.clone(),
         };
+        let task = self.db.create_task(&new, self.clock.now())?;
 
-        let task_clone = task.clone();
-        self.db.update_task(&task_clone)?;
-        self.status_message = Some(status_msg.to_string());
+        let mut detail = format!("difficulty {}", task.difficulty);
+        if task.estimate_minutes.is_some() {
+            detail.push_str(&format!(", estimate {}", format_estimate(task.estimate_minutes)));
+        }
+        if let Some(parent_id) = task.parent_id {
+            detail.push_str(&format!(", subtask of #{}", parent_id));
+        }
+        if !task.depends_on.is_empty() {
+            detail.push_str(&format!(", after {}", models::format_task_ids(&task.depends_on)));
+        }
+        if task.due_date.is_some() {
+            detail.push_str(&format!(", due {}", format_due(task.due_date)));
+        }
+        if let Some(rule) = &task.recurrence {
+            detail.push_str(&format!(", repeats {}", rule));
+        }
+        self.db.record_event(task.id, TaskEventKind::Created, task.created_at, Some(detail))?;
+        self.record_undo(format!("add '{}'", task.title), task.id, before)?;
+        self.status_message = Some(format!("Task '{}' added!", task.title));
+        self.refresh_tasks()?;
+        Ok(task)
+    }
 
+    fn find_task(&self, task_id: i64) -> Result<Task> {
+        self.db
+            .get_task(task_id)?
+            .ok_or_else(|| anyhow!("Task #{} not found", task_id))
+    }
+
+    pub fn delete_selected_task(&mut self) -> Result<()> {
+        if let Some(task_id) = self.selected_task().map(|t| t.id) {
+            match self.delete_task(task_id) {
+                Ok(_) => self.clamp_selection(),
+                Err(err) => self.status_message = Some(err.to_string()),
+            }
+        }
         Ok(())
     }
 
-    pub fn complete_task(&mut self) -> Result<()> {
-        if self.selected_task_index >= self.tasks.len() {
+    pub fn delete_task(&mut self, task_id: i64) -> Result<Task> {
+        let task = self.find_task(task_id)?;
+        let subtasks = self.db.count_subtasks(task_id)?;
+        if subtasks > 0 {
+            bail!("Task #{} has {} subtasks; delete them first", task_id, subtasks);
+        }
+        // Deleting a prerequisite would silently unblock its dependents, and
+        // undo could not bring the dependency back
+        let dependents = self.db.get_dependents(task_id)?;
+        if !dependents.is_empty() {
+            bail!(
+                "Task #{} is a prerequisite of {}; remove the dependency first",
+                task_id,
+                models::format_task_ids(&dependents)
+            );
+        }
+        let before = self.snapshot(task_id)?;
+
+        self.atomically(|app| {
+            app.db.delete_task(task_id)?;
+            app.db.record_event(task_id, TaskEventKind::Deleted, app.clock.now(), Some(task.title.clone()))?;
+            app.record_undo(format!("delete '{}'", task.title), task_id, before)
+        })?;
+        self.status_message = Some(format!("Task '{}' deleted (u to undo)", task.title));
+        self.refresh_tasks()?;
+        Ok(task)
+    }
+
+    /// Start, pause or resume the selected task depending on its state
+    pub fn toggle_selected_task(&mut self) -> Result<()> {
+        let Some(task) = self.selected_task() else {
             return Ok(());
+        };
+
+        if task.is_blocked() && task.status != TaskStatus::InProgress {
+            self.status_message = Some(format!(
+                "Blocked: waiting on {}",
+                models::format_task_ids(&task.blocked_by)
+            ));
+            return Ok(());
+        }
+
+        match task.status {
+            TaskStatus::Pending | TaskStatus::Paused => self.start_task(task.id)?,
+            TaskStatus::InProgress => self.pause_task(task.id)?,
+            TaskStatus::Completed => return Ok(()),
+        };
+        Ok(())
+    }
+
+    /// Start a pending task or resume a paused one
+    pub fn start_task(&mut self, task_id: i64) -> Result<Task> {
+        let now = self.clock.now();
+        let mut task = self.find_task(task_id)?;
+        let before = self.snapshot(task_id)?;
+
+        if task.is_blocked() {
+            bail!("Task #{} is blocked by {}", task_id, models::format_task_ids(&task.blocked_by));
+        }
+
+        let (event, verb, status_msg) = match task.status {
+            TaskStatus::Pending => {
+                task.started_at = Some(now);
+                (TaskEventKind::Started, "start", "Task started!")
+            }
+            TaskStatus::Paused => (TaskEventKind::Resumed, "resume", "Task resumed!"),
+            TaskStatus::InProgress => bail!("Task #{} is already in progress", task_id),
+            TaskStatus::Completed => bail!("Task #{} is already completed", task_id),
+        };
+
+        task.status = TaskStatus::InProgress;
+        let entry = self.db.start_time_entry(task.id, now)?;
+        task.time_entries.push(entry);
+
+        self.finish_transition(&task, event, verb, status_msg, now, before)?;
+        Ok(task)
+    }
+
+    pub fn pause_task(&mut self, task_id: i64) -> Result<Task> {
+        let now = self.clock.now();
+        let mut task = self.find_task(task_id)?;
+        let before = self.snapshot(task_id)?;
+
+        if task.status != TaskStatus::InProgress {
+            bail!("Task #{} is not in progress", task_id);
         }
 
-        let task = &mut self.tasks[self.selected_task_index];
+        task.status = TaskStatus::Paused;
+        self.db.stop_time_entry(task.id, now)?;
+        task.close_time_entry(now);
+
+        self.finish_transition(&task, TaskEventKind::Paused, "pause", "Task paused", now, before)?;
+        Ok(task)
+    }
+
+    fn finish_transition(
+        &mut self,
+        task: &Task,
+        event: TaskEventKind,
+        verb: &str,
+        status_msg: &str,
+        now: DateTime<Utc>,
+        before: Snapshot,
+    ) -> Result<()> {
+        self.db.update_task(task)?;
+        self.db.record_event(task.id, event, now, None)?;
+        self.record_undo(format!("{} '{}'", verb, task.title), task.id, before)?;
+        self.status_message = Some(status_msg.to_string());
+        self.refresh_tasks()?;
+        Ok(())
+    }
+
+    pub fn complete_selected_task(&mut self) -> Result<()> {
+        let Some(task) = self.selected_task() else {
+            return Ok(());
+        };
 
         if task.status == TaskStatus::Completed {
             self.status_message = Some("Task already completed!".to_string());
             return Ok(());
         }
+        if task.is_blocked() {
+            self.status_message = Some(format!(
+                "Blocked: waiting on {}",
+                models::format_task_ids(&task.blocked_by)
+            ));
+            return Ok(());
+        }
+        if let Some(progress) = self.subtask_progress.get(&task.id).filter(|p| !p.is_finished()) {
+            self.status_message = Some(format!(
+                "Finish the subtasks first ({}/{} done)",
+                progress.done, progress.total
+            ));
+            return Ok(());
+        }
 
-        // Handle paused state
-        if task.status == TaskStatus::Paused {
-            if let Some(paused_at) = task.paused_at {
-                let pause_duration = (Utc::now() - paused_at).num_seconds();
-                task.total_pause_duration += pause_duration;
-            }
+        self.complete_task(task.id)?;
+
+        // Move selection if we're hiding completed tasks
+        self.clamp_selection();
+
+        Ok(())
+    }
+
+    pub fn complete_task(&mut self, task_id: i64) -> Result<Task> {
+        let now = self.clock.now();
+        let mut task = self.find_task(task_id)?;
+        let before = self.snapshot(task_id)?;
+
+        if task.status == TaskStatus::Completed {
+            bail!("Task #{} is already completed", task_id);
+        }
+        if task.is_blocked() {
+            bail!("Task #{} is blocked by {}", task_id, models::format_task_ids(&task.blocked_by));
+        }
+        if let Some(progress) = self.db.get_subtask_progress()?.get(&task_id).filter(|p| !p.is_finished()) {
+            bail!(
+                "Task #{} has unfinished subtasks ({}/{} done)",
+                task_id,
+                progress.done,
+                progress.total
+            );
+        }
+
+        // Work out the next occurrence up front so a rule that cannot repeat
+        // again fails before anything is written
+        let next_due = self.next_due_date(&task, now)?;
+
+        // Close the running interval, if any
+        let was_running = task.status == TaskStatus::InProgress;
+        if was_running {
+            task.close_time_entry(now);
         }
 
         // Mark as completed
         task.status = TaskStatus::Completed;
-        task.completed_at = Some(Utc::now());
-
-        // Get task data before releasing borrow
-        let task_difficulty = task.difficulty;
-        let mut task_clone = task.clone();
+        task.completed_at = Some(now);
 
         // Calculate points
-        let avg_times = self.db.get_avg_time_by_difficulty()?;
+        let avg_times = self.db.get_avg_time_by_difficulty(None)?;
         let avg_time = avg_times
             .iter()
-            .find(|(diff, _)| *diff == task_difficulty)
+            .find(|(diff, _)| *diff == task.difficulty)
             .map(|(_, time)| *time);
 
-        let points = scoring::calculate_task_points(&task_clone, &self.user_stats, avg_time);
-        task_clone.points_earned = points;
-
-        // Update stats
-        scoring::update_stats_on_completion(&mut self.user_stats, &task_clone, points);
+        let breakdown = self
+            .strategy
+            .calculate_task_points(&task, &self.user_stats, avg_time);
+        let points = breakdown.total;
+        task.points_earned = points;
+        task.points_breakdown = Some(breakdown);
 
-        // Update the task in our vector
-        self.tasks[self.selected_task_index] = task_clone.clone();
+        // Update a copy of the stats; it only replaces ours once saved
+        let mut stats = self.user_stats.clone();
+        let milestones = self
+            .strategy
+            .update_stats_on_completion(&mut stats, &task, points);
 
         // Save to database
-        self.db.update_task(&task_clone)?;
-        self.db.update_user_stats(&self.user_stats)?;
+        let next = self.atomically(|app| {
+            if was_running {
+                app.db.stop_time_entry(task.id, now)?;
+            }
+            app.db.update_task(&task)?;
+            app.db.update_user_stats(&stats)?;
+            app.db.record_points(Some(task.id), &breakdown.grants(), now)?;
+            app.db.record_points(Some(task.id), &milestones, now)?;
+            app.db.record_event(
+                task.id,
+                TaskEventKind::Completed,
+                now,
+                Some(format!("+{} points", points)),
+            )?;
+            app.record_undo(format!("complete '{}'", task.title), task.id, before)?;
+            app.spawn_next_instance(&task, next_due, now)
+        })?;
+        self.user_stats = stats;
 
-        self.status_message = Some(format!("Task completed! +{} points", points));
+        let milestone_points = scoring::sum_points(&milestones);
+        let mut message = if milestone_points > 0 {
+            format!(
+                "Task completed! +{} points ({}) +{} milestone bonus!",
+                points,
+                breakdown.summary(),
+                milestone_points
+            )
+        } else {
+            format!("Task completed! +{} points ({})", points, breakdown.summary())
+        };
+        if let Some(due) = next.and_then(|n| n.due_date) {
+            message.push_str(&format!(" Next one due {}", due.format("%a %Y-%m-%d")));
+        }
+        self.status_message = Some(message);
         self.refresh_tasks()?;
         self.refresh_stats()?;
 
-        // Move selection if we're hiding completed tasks
-        if !self.show_completed && self.selected_task_index >= self.tasks.len() && !self.tasks.is_empty() {
-            self.selected_task_index = self.tasks.len() - 1;
+        Ok(task)
+    }
+
+    pub fn rebuild_stats(&mut self) -> Result<()> {
+        let before = self.user_stats.total_points;
+        self.user_stats = self.db.rebuild_user_stats()?;
+        self.status_message = Some(format!(
+            "Stats rebuilt from ledger: {} → {} points",
+            before, self.user_stats.total_points
+        ));
+        Ok(())
+    }
+
+    /// Replay every completed task through the active strategy without
+    /// touching the database
+    pub fn plan_recompute(&self) -> Result<Recompute> {
+        let history = self.db.get_completion_history()?;
+        let mut plan = recompute::replay(&history, self.db.get_user_stats()?, self.strategy.as_ref());
+
+        // Ledger rows outside the replay (imported totals, deleted tasks) are
+        // kept as they are, so they still count
+        let rescored: HashSet<i64> = history.iter().map(|task| task.id).collect();
+        plan.after.total_points += self.db.get_ledger_points_excluding(&rescored)?;
+        Ok(plan)
+    }
+
+    /// Write a planned recompute back and remember the strategy it used
+    pub fn apply_recompute(&mut self, plan: &Recompute) -> Result<()> {
+        self.db.apply_recompute(plan)?;
+        self.db.set_setting(STRATEGY_SETTING, self.strategy.kind().as_str())?;
+        self.previous_strategy = None;
+
+        // Snapshots taken before the rewrite would restore stale points
+        self.undo_stack.clear();
+        self.refresh_tasks()?;
+        self.refresh_stats()?;
+        Ok(())
+    }
+
+    /// Compute a recompute and ask for confirmation if it changes anything
+    pub fn preview_recompute(&mut self) -> Result<()> {
+        let plan = self.plan_recompute()?;
+        if plan.is_unchanged() {
+            if self.previous_strategy.take().is_some() {
+                self.db.set_setting(STRATEGY_SETTING, self.strategy.kind().as_str())?;
+            }
+            self.status_message = Some("Points and stats already match task history".to_string());
+            return Ok(());
         }
 
+        self.pending_recompute = Some(plan);
+        self.input_mode = InputMode::ConfirmRecompute;
+        Ok(())
+    }
+
+    pub fn confirm_recompute(&mut self) -> Result<()> {
+        self.input_mode = InputMode::Normal;
+        let Some(plan) = self.pending_recompute.take() else {
+            return Ok(());
+        };
+
+        self.apply_recompute(&plan)?;
+        self.status_message = Some(format!(
+            "Recomputed {} tasks with '{}': {} → {} points",
+            plan.tasks.len(),
+            self.strategy.kind().as_str(),
+            plan.before.total_points,
+            plan.after.total_points
+        ));
+        Ok(())
+    }
+
+    pub fn decline_recompute(&mut self) -> Result<()> {
+        self.input_mode = InputMode::Normal;
+        self.pending_recompute = None;
+        self.status_message = Some(if self.previous_strategy.take().is_some() {
+            self.db.set_setting(STRATEGY_SETTING, self.strategy.kind().as_str())?;
+            "Keeping existing points; new tasks use the new strategy".to_string()
+        } else {
+            "Recompute cancelled".to_string()
+        });
+        Ok(())
+    }
+
+    fn snapshot(&self, task_id: i64) -> Result<Snapshot> {
+        Ok(Snapshot {
+            task: self.db.get_task(task_id)?,
+            ledger: self.db.get_task_ledger(task_id)?,
+            user_stats: self.db.get_user_stats()?,
+        })
+    }
+
+    /// Run `op` as one database transaction. If any step fails, its writes
+    /// are rolled back and the in-memory stats and undo history are put back
+    /// as they were, so memory and database keep agreeing.
+    fn atomically<T>(&mut self, op: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let user_stats = self.user_stats.clone();
+        let undo_stack = self.undo_stack.clone();
+
+        self.db.begin()?;
+        match op(self).and_then(|value| self.db.commit().map(|_| value)) {
+            Ok(value) => Ok(value),
+            Err(err) => {
+                self.db.rollback()?;
+                self.user_stats = user_stats;
+                self.undo_stack = undo_stack;
+                Err(err)
+            }
+        }
+    }
+
+    fn record_undo(&mut self, description: String, task_id: i64, before: Snapshot) -> Result<()> {
+        let after = self.snapshot(task_id)?;
+        self.undo_stack.record(UndoEntry {
+            description,
+            task_id,
+            before,
+            after,
+            linked: Vec::new(),
+        });
+        Ok(())
+    }
+
+    pub fn undo(&mut self) -> Result<()> {
+        let restored = self.atomically(|app| {
+            let Some(entry) = app.undo_stack.take_undo() else {
+                return Ok(None);
+            };
+            for linked in entry.linked.iter().rev() {
+                app.restore(&linked.before, linked.task_id, TaskEventKind::Undone, &linked.description)?;
+            }
+            app.restore(&entry.before, entry.task_id, TaskEventKind::Undone, &entry.description)?;
+            let restored = (entry.task_id, format!("Undid {}", entry.description));
+            app.undo_stack.push_undone(entry);
+            Ok(Some(restored))
+        })?;
+        self.show_restored(restored, "Nothing to undo")
+    }
+
+    pub fn redo(&mut self) -> Result<()> {
+        let restored = self.atomically(|app| {
+            let Some(entry) = app.undo_stack.take_redo() else {
+                return Ok(None);
+            };
+            app.restore(&entry.after, entry.task_id, TaskEventKind::Redone, &entry.description)?;
+            for linked in &entry.linked {
+                app.restore(&linked.after, linked.task_id, TaskEventKind::Redone, &linked.description)?;
+            }
+            let restored = (entry.task_id, format!("Redid {}", entry.description));
+            app.undo_stack.push_redone(entry);
+            Ok(Some(restored))
+        })?;
+        self.show_restored(restored, "Nothing to redo")
+    }
+
+    /// Put one task, its ledger rows and the stats back as `snapshot` has
+    /// them. Callers run this inside `atomically`.
+    fn restore(
+        &mut self,
+        snapshot: &Snapshot,
+        task_id: i64,
+        event: TaskEventKind,
+        description: &str,
+    ) -> Result<()> {
+        self.db.restore_task(task_id, snapshot.task.as_ref(), &snapshot.ledger)?;
+        self.db.update_user_stats(&snapshot.user_stats)?;
+        self.db.record_event(task_id, event, self.clock.now(), Some(description.to_string()))?;
+        Ok(())
+    }
+
+    /// Reload after an undo or redo and keep the restored task selected
+    /// when it is visible
+    fn show_restored(&mut self, restored: Option<(i64, String)>, nothing: &str) -> Result<()> {
+        let Some((task_id, message)) = restored else {
+            self.status_message = Some(nothing.to_string());
+            return Ok(());
+        };
+        self.status_message = Some(message);
+        self.refresh_tasks()?;
+        self.refresh_stats()?;
+
+        if let Some(index) = self.visible_tasks().iter().position(|t| t.id == task_id) {
+            self.selected_task_index = index;
+        } else {
+            self.clamp_selection();
+        }
         Ok(())
     }
 
@@ -314,4 +1521,379 @@ impl App {
     pub fn switch_view(&mut self, view: View) {
         self.current_view = view;
     }
+
+    pub fn start_tag_filter(&mut self) -> Result<()> {
+        self.available_tags = self.db.get_tags_in_use()?;
+        if self.available_tags.is_empty() {
+            self.status_message = Some("No tags yet - add #tags to a task title".to_string());
+            return Ok(());
+        }
+        self.tag_cursor = self.tag_cursor.min(self.available_tags.len() - 1);
+        self.input_mode = InputMode::FilteringTags;
+        Ok(())
+    }
+
+    pub fn move_tag_cursor(&mut self, down: bool) {
+        if down {
+            if self.tag_cursor + 1 < self.available_tags.len() {
+                self.tag_cursor += 1;
+            }
+        } else {
+            self.tag_cursor = self.tag_cursor.saturating_sub(1);
+        }
+    }
+
+    pub fn toggle_tag_filter(&mut self) {
+        if let Some(tag) = self.available_tags.get(self.tag_cursor) {
+            if !self.tag_filter.remove(tag) {
+                self.tag_filter.insert(tag.clone());
+            }
+            self.clamp_selection();
+        }
+    }
+
+    pub fn clear_tag_filter(&mut self) {
+        self.tag_filter.clear();
+    }
+
+    pub fn close_tag_filter(&mut self) {
+        self.input_mode = InputMode::Normal;
+        self.clamp_selection();
+    }
+
+    /// Open the search prompt, keeping the previous query and its results
+    pub fn start_search(&mut self) {
+        self.input_mode = InputMode::Searching;
+        self.update_search();
+    }
+
+    /// Re-run the search for the current query; called on every keystroke
+    pub fn update_search(&mut self) {
+        match self.db.search_tasks(&self.search_input, SEARCH_LIMIT) {
+            Ok(hits) => self.search_results = hits,
+            Err(e) => {
+                self.search_results.clear();
+                self.status_message = Some(format!("Search failed: {}", e));
+            }
+        }
+        self.search_cursor = 0;
+    }
+
+    pub fn search_input_char(&mut self, c: char) {
+        self.search_input.push(c);
+        self.update_search();
+    }
+
+    pub fn search_backspace(&mut self) {
+        self.search_input.pop();
+        self.update_search();
+    }
+
+    pub fn clear_search(&mut self) {
+        self.search_input.clear();
+        self.update_search();
+    }
+
+    pub fn move_search_cursor(&mut self, down: bool) {
+        if down {
+            if self.search_cursor + 1 < self.search_results.len() {
+                self.search_cursor += 1;
+            }
+        } else {
+            self.search_cursor = self.search_cursor.saturating_sub(1);
+        }
+    }
+
+    pub fn close_search(&mut self) {
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Close the search and select the highlighted task in the task list,
+    /// showing completed tasks, dropping filters and expanding parents as
+    /// needed to reveal it
+    pub fn open_search_result(&mut self) -> Result<()> {
+        self.input_mode = InputMode::Normal;
+        let Some(task) = self.search_results.get(self.search_cursor).map(|hit| hit.task.clone()) else {
+            return Ok(());
+        };
+
+        if task.status == TaskStatus::Completed && !self.show_completed {
+            self.show_completed = true;
+            self.refresh_tasks()?;
+        }
+        if !self.tag_filter.is_empty() && !task.tags.iter().any(|t| self.tag_filter.contains(t)) {
+            self.tag_filter.clear();
+        }
+        if self.project_scope().is_some_and(|id| task.project_id != Some(id)) {
+            self.current_project = None;
+        }
+        let mut parent = task.parent_id;
+        while let Some(id) = parent {
+            self.collapsed.remove(&id);
+            parent = self.tasks.iter().find(|t| t.id == id).and_then(|t| t.parent_id);
+        }
+
+        self.current_view = View::TaskList;
+        if let Some(index) = self.visible_tasks().iter().position(|t| t.id == task.id) {
+            self.selected_task_index = index;
+        }
+        Ok(())
+    }
+
+    /// Start typing a fuzzy filter, continuing the current one
+    pub fn start_fuzzy_filter(&mut self) {
+        self.input_mode = InputMode::FuzzyFiltering;
+    }
+
+    pub fn fuzzy_filter_char(&mut self, c: char) {
+        let mut filter = self.fuzzy_filter.clone();
+        filter.push(c);
+        self.set_fuzzy_filter(filter);
+    }
+
+    pub fn fuzzy_filter_backspace(&mut self) {
+        let mut filter = self.fuzzy_filter.clone();
+        filter.pop();
+        self.set_fuzzy_filter(filter);
+    }
+
+    /// Stop typing but keep the list filtered
+    pub fn keep_fuzzy_filter(&mut self) {
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Drop the filter and show the full list again
+    pub fn clear_fuzzy_filter(&mut self) {
+        self.input_mode = InputMode::Normal;
+        self.set_fuzzy_filter(String::new());
+    }
+
+    /// Change the fuzzy filter, keeping the selected task selected while it
+    /// is still listed and otherwise selecting the best match
+    fn set_fuzzy_filter(&mut self, filter: String) {
+        let selected = self.selected_task().map(|t| t.id);
+        self.fuzzy_filter = filter;
+        self.selected_task_index = selected
+            .and_then(|id| self.visible_tasks().iter().position(|t| t.id == id))
+            .unwrap_or(0);
+    }
+
+    pub fn start_project_picker(&mut self) -> Result<()> {
+        self.projects = self.db.get_projects()?;
+        self.project_cursor = match self.project_scope() {
+            Some(id) => self.projects.iter().position(|p| p.id == id).map_or(0, |i| i + 1),
+            None => 0,
+        };
+        self.input_mode = InputMode::PickingProject;
+        Ok(())
+    }
+
+    pub fn move_project_cursor(&mut self, down: bool) {
+        // One row per project plus "All projects" and "New project"
+        let last = self.projects.len() + 1;
+        if down {
+            self.project_cursor = (self.project_cursor + 1).min(last);
+        } else {
+            self.project_cursor = self.project_cursor.saturating_sub(1);
+        }
+    }
+
+    /// The project under the picker cursor: Some(None) for "All projects",
+    /// None for "New project"
+    fn project_at_cursor(&self) -> Option<Option<&Project>> {
+        match self.project_cursor {
+            0 => Some(None),
+            i => self.projects.get(i - 1).map(Some),
+        }
+    }
+
+    /// Switch to the highlighted project, or start naming a new one
+    pub fn select_project(&mut self) {
+        match self.project_at_cursor() {
+            Some(project) => {
+                self.current_project = project.cloned();
+                self.input_mode = InputMode::Normal;
+                self.selected_task_index = 0;
+                self.status_message = Some(match &self.current_project {
+                    Some(project) => format!("Switched to project '{}'", project.name),
+                    None => "Showing all projects".to_string(),
+                });
+            }
+            None => {
+                self.input_buffer.clear();
+                self.input_mode = InputMode::NamingProject;
+            }
+        }
+    }
+
+    pub fn submit_project_name(&mut self) -> Result<()> {
+        let name = self.input_buffer.trim().to_string();
+        self.input_buffer.clear();
+        self.input_mode = InputMode::Normal;
+        if name.is_empty() {
+            return Ok(());
+        }
+
+        let project = self.db.get_or_create_project(&name, self.clock.now())?;
+        self.status_message = Some(format!("Switched to project '{}'", project.name));
+        self.current_project = Some(project);
+        self.selected_task_index = 0;
+        Ok(())
+    }
+
+    /// Move the selected task, with all of its subtasks, into the highlighted
+    /// project ("All projects" takes them out of their project)
+    pub fn move_selected_task_to_project(&mut self) -> Result<()> {
+        let Some(target) = self.project_at_cursor().map(|p| p.cloned()) else {
+            return Ok(());
+        };
+        let Some(task_id) = self.selected_task().map(|t| t.id) else {
+            return Ok(());
+        };
+        self.input_mode = InputMode::Normal;
+
+        let task = self.find_task(task_id)?;
+        // Subtasks live in their parent's project
+        if task.parent_id.is_some() {
+            self.status_message = Some("Subtasks stay in their parent's project; move the parent instead".to_string());
+            return Ok(());
+        }
+        if task.project_id == target.as_ref().map(|p| p.id) {
+            return Ok(());
+        }
+
+        let now = self.clock.now();
+        let before = self.snapshot(task_id)?;
+        let task = self.set_task_project(task, &target, now)?;
+        self.record_undo(format!("move '{}'", task.title), task_id, before)?;
+
+        let descendants = self.db.get_descendant_ids(task_id)?;
+        for &id in &descendants {
+            let before = self.snapshot(id)?;
+            let subtask = self.find_task(id)?;
+            let subtask = self.set_task_project(subtask, &target, now)?;
+            let entry = UndoEntry {
+                description: format!("move '{}'", subtask.title),
+                task_id: id,
+                before,
+                after: self.snapshot(id)?,
+                linked: Vec::new(),
+            };
+            self.undo_stack.link_to_last(entry);
+        }
+
+        let destination = target.map_or_else(|| "none".to_string(), |p| p.name);
+        self.status_message = Some(match descendants.len() {
+            0 => format!("Moved '{}' to {}", task.title, destination),
+            count => format!("Moved '{}' and {} subtasks to {}", task.title, count, destination),
+        });
+        self.refresh_tasks()?;
+        self.clamp_selection();
+        Ok(())
+    }
+
+    /// Put one task in `target` (or no project) and log the change
+    fn set_task_project(&mut self, mut task: Task, target: &Option<Project>, now: DateTime<Utc>) -> Result<Task> {
+        let name = |project: &Option<String>| project.clone().unwrap_or_else(|| "none".to_string());
+        let change = format!("project {} → {}", name(&task.project), name(&target.as_ref().map(|p| p.name.clone())));
+        task.project_id = target.as_ref().map(|p| p.id);
+        task.project = target.as_ref().map(|p| p.name.clone());
+
+        self.db.update_task(&task)?;
+        self.db.record_event(task.id, TaskEventKind::Edited, now, Some(change))?;
+        Ok(task)
+    }
+}
+
+fn format_due(due: Option<NaiveDate>) -> String {
+    due.map_or_else(|| "none".to_string(), |d| d.format("%Y-%m-%d").to_string())
+}
+
+fn format_estimate(estimate_minutes: Option<i64>) -> String {
+    estimate_minutes.map_or_else(|| "none".to_string(), models::format_minutes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FakeClock;
+    use chrono::TimeZone;
+
+    fn test_app() -> App {
+        let clock = FakeClock::new(Utc.with_ymd_and_hms(2026, 3, 4, 9, 0, 0).unwrap());
+        let db = Database::open_in_memory(clock.now()).unwrap();
+        App::open(Config::default(), db, Box::new(clock)).unwrap()
+    }
+
+    fn add(app: &mut App, title: &str) -> i64 {
+        app.add_task(NewTask::new(title.to_string(), 3)).unwrap().id
+    }
+
+    #[test]
+    fn a_task_cannot_depend_on_itself() {
+        let mut app = test_app();
+        let a = add(&mut app, "A");
+
+        let err = app.set_dependencies(a, vec![a]).unwrap_err();
+        assert_eq!(err.to_string(), format!("Task #{} cannot depend on itself", a));
+    }
+
+    #[test]
+    fn indirect_cycles_are_rejected_with_their_chain() {
+        let mut app = test_app();
+        let (a, b, c) = (add(&mut app, "A"), add(&mut app, "B"), add(&mut app, "C"));
+        app.set_dependencies(a, vec![b]).unwrap();
+        app.set_dependencies(b, vec![c]).unwrap();
+
+        let err = app.set_dependencies(c, vec![a]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("Task #{c} cannot depend on #{a}: that would form a cycle #{c} → #{a} → #{b} → #{c}")
+        );
+        assert!(app.find_task(c).unwrap().depends_on.is_empty());
+    }
+
+    #[test]
+    fn dependencies_on_missing_tasks_are_rejected() {
+        let mut app = test_app();
+        let a = add(&mut app, "A");
+
+        let err = app.set_dependencies(a, vec![99]).unwrap_err();
+        assert_eq!(err.to_string(), "Task #99 not found");
+
+        let mut new = NewTask::new("B".to_string(), 3);
+        new.depends_on = vec![99];
+        assert!(app.add_task(new).is_err());
+        assert_eq!(app.db.get_all_tasks().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn prerequisites_cannot_be_deleted() {
+        let mut app = test_app();
+        let (a, b) = (add(&mut app, "A"), add(&mut app, "B"));
+        app.set_dependencies(b, vec![a]).unwrap();
+
+        let err = app.delete_task(a).unwrap_err();
+        assert_eq!(err.to_string(), format!("Task #{a} is a prerequisite of #{b}; remove the dependency first"));
+        assert!(app.find_task(a).is_ok());
+    }
+
+    #[test]
+    fn the_next_instance_stays_in_the_project() {
+        let mut app = test_app();
+        let now = app.clock.now();
+        let project = app.db.get_or_create_project("Chores", now).unwrap();
+        let mut new = NewTask::new("Water plants".to_string(), 2);
+        new.project = Some(project.clone());
+        new.due_date = Some(now.date_naive());
+        new.recurrence = models::parse_recurrence("weekly", now.date_naive()).unwrap();
+        let done = app.add_task(new).unwrap();
+
+        app.start_task(done.id).unwrap();
+        app.complete_task(done.id).unwrap();
+
+        let next = app.db.get_all_tasks().unwrap().into_iter().find(|t| t.id != done.id).unwrap();
+        assert_eq!(next.series_id, done.series_id);
+        assert_eq!(next.project_id, Some(project.id));
+    }
 }
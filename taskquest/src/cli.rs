@@ -0,0 +1,818 @@
+use crate::app::{App, SortKey, SortMode};
+use crate::models::{self, EstimateAccuracy, NewTask, Project, ProjectStats, SearchHit, SeriesStats, TagStats, Task, TaskStatus, UserStats};
+use crate::recompute::Recompute;
+use crate::status::StatusArgs;
+use anyhow::{anyhow, bail, Result};
+use clap::builder::PossibleValue;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+
+/// Gamified task manager. Without a subcommand the TUI is started.
+#[derive(Debug, Parser)]
+#[command(name = "taskquest", version, about)]
+pub struct Cli {
+    /// Print machine-readable JSON instead of text
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Invocation>,
+}
+
+/// Top-level subcommands. `status` is kept apart from the rest because it
+/// runs without opening the app.
+#[derive(Debug, Subcommand)]
+pub enum Invocation {
+    #[command(flatten)]
+    App(Command),
+    /// Print a one-line summary of the running task for tmux or zellij
+    Status(StatusArgs),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Add a new task; `#words` in the title become tags
+    Add {
+        title: String,
+        /// Difficulty from 1 (trivial) to 10 (very hard)
+        #[arg(short, long, default_value_t = 5, value_parser = clap::value_parser!(i32).range(1..=10))]
+        difficulty: i32,
+        /// Put the task in this project, creating it if needed
+        #[arg(short, long)]
+        project: Option<String>,
+        /// Add it as a subtask of this task (inherits its project)
+        #[arg(long)]
+        parent: Option<i64>,
+        /// Task that must be completed first (repeatable)
+        #[arg(long = "after", value_name = "ID")]
+        after: Vec<i64>,
+        /// Due date: today, tomorrow, a weekday (fri), +3d, +2w or YYYY-MM-DD
+        #[arg(long, value_name = "WHEN")]
+        due: Option<String>,
+        /// Repeat rule: daily, weekdays, "weekly mon,thu", monthly, "monthly 15" or "every 3 days"
+        #[arg(long, value_name = "RULE")]
+        repeat: Option<String>,
+        /// Free-form notes; `-` reads them from stdin
+        #[arg(short, long)]
+        notes: Option<String>,
+        /// Time estimate: 30m, 2h, 1h30m or 1.5h
+        #[arg(short, long, value_name = "TIME")]
+        estimate: Option<String>,
+    },
+    /// List tasks (pending ones unless --all is given)
+    List {
+        /// Include completed tasks
+        #[arg(short, long)]
+        all: bool,
+        /// Only tasks with this tag (repeatable; any match)
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+        /// Only tasks in this project
+        #[arg(short, long)]
+        project: Option<String>,
+        /// Order of the list; defaults to the order last picked in the TUI
+        #[arg(short, long, value_enum, value_name = "KEY")]
+        sort: Option<SortKey>,
+        /// Reverse the order
+        #[arg(short, long)]
+        reverse: bool,
+        /// Second key for tasks that tie on the first
+        #[arg(long, value_enum, value_name = "KEY")]
+        then: Option<SortKey>,
+    },
+    /// Start a pending task or resume a paused one
+    Start { id: i64 },
+    /// Pause a task in progress
+    Pause { id: i64 },
+    /// Complete a task and award its points
+    Done { id: i64 },
+    /// Delete a task
+    Delete { id: i64 },
+    /// Show or replace the tasks a task depends on
+    Depends {
+        id: i64,
+        /// New prerequisites; replaces the current ones
+        prerequisites: Vec<i64>,
+        /// Remove all prerequisites
+        #[arg(long, conflicts_with = "prerequisites")]
+        clear: bool,
+    },
+    /// Show, set or clear the due date of a task
+    Due {
+        id: i64,
+        /// today, tomorrow, a weekday (fri), +3d, +2w or YYYY-MM-DD
+        when: Option<String>,
+        /// Remove the due date
+        #[arg(long, conflicts_with = "when")]
+        clear: bool,
+    },
+    /// Show, set or stop the repeat rule of a task
+    Repeat {
+        id: i64,
+        /// daily, weekdays, "weekly mon,thu", monthly, "monthly 15" or "every 3 days"
+        rule: Option<String>,
+        /// Stop repeating; completing the task no longer creates the next one
+        #[arg(long, conflicts_with = "rule")]
+        stop: bool,
+    },
+    /// Show, replace or clear the notes of a task
+    Notes {
+        id: i64,
+        /// New notes; `-` reads them from stdin
+        text: Option<String>,
+        /// Remove the notes
+        #[arg(long, conflicts_with = "text")]
+        clear: bool,
+    },
+    /// Show, set or clear the time estimate of a task
+    Estimate {
+        id: i64,
+        /// 30m, 2h, 1h30m or 1.5h
+        time: Option<String>,
+        /// Remove the estimate
+        #[arg(long, conflicts_with = "time")]
+        clear: bool,
+    },
+    /// Find tasks, completed ones included, by words in their title or notes
+    Search {
+        /// Words to look for; each also matches longer words it starts
+        #[arg(required = true)]
+        words: Vec<String>,
+        /// Show at most this many matches
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Show points, streaks and totals
+    Stats {
+        /// Only the points, tasks and focus time of this project
+        #[arg(short, long)]
+        project: Option<String>,
+    },
+    /// List projects with their points and focus time
+    Projects,
+    /// Show how long each difficulty level really takes and flag levels that overlap
+    Calibration {
+        /// Only tasks in this project
+        #[arg(short, long)]
+        project: Option<String>,
+    },
+    /// Rescore all completed tasks from their history and rebuild stats
+    Recompute {
+        /// Apply without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+        /// Only show what would change
+        #[arg(long, conflicts_with = "yes")]
+        dry_run: bool,
+    },
+}
+
+/// `--sort`/`--then` accept the names the TUI saves sort orders under
+impl ValueEnum for SortKey {
+    fn value_variants<'a>() -> &'a [Self] {
+        &SortKey::ALL
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.as_str()).help(self.description()))
+    }
+}
+
+pub fn run(app: &mut App, command: Command, json: bool) -> Result<()> {
+    match command {
+        Command::Add { title, difficulty, project, parent, after, due, repeat, notes, estimate } => {
+            let (title, tags) = models::split_tags(&title);
+            if title.is_empty() {
+                bail!("Task title cannot be empty");
+            }
+            let project = match project {
+                Some(name) => Some(app.db.get_or_create_project(name.trim(), app.clock.now())?),
+                None => None,
+            };
+            let mut new = NewTask::new(title, difficulty);
+            new.tags = tags;
+            new.project = project;
+            new.parent_id = parent;
+            new.depends_on = after;
+            new.depends_on.sort_unstable();
+            new.depends_on.dedup();
+            if let Some(due) = due {
+                new.due_date = models::parse_due_date(&due, app.today()).map_err(|e| anyhow!(e))?;
+            }
+            if let Some(rule) = repeat {
+                let anchor = new.due_date.unwrap_or(app.today());
+                new.recurrence = models::parse_recurrence(&rule, anchor).map_err(|e| anyhow!(e))?;
+            }
+            if let Some(notes) = notes {
+                new.notes = read_text(notes)?.trim_end().to_string();
+            }
+            if let Some(estimate) = estimate {
+                new.estimate_minutes = models::parse_estimate(&estimate).map_err(|e| anyhow!(e))?;
+            }
+            let task = app.add_task(new)?;
+            let mut message = format!("Added #{} '{}' (difficulty {})", task.id, task.title, task.difficulty);
+            if let Some(parent) = task.parent_id {
+                message.push_str(&format!(" under #{}", parent));
+            }
+            if let Some(project) = &task.project {
+                message.push_str(&format!(" in {}", project));
+            }
+            if !task.depends_on.is_empty() {
+                message.push_str(&format!(" after {}", models::format_task_ids(&task.depends_on)));
+            }
+            if let Some(due) = task.due_date {
+                message.push_str(&format!(", due {}", due.format("%a %Y-%m-%d")));
+            }
+            if let Some(rule) = &task.recurrence {
+                message.push_str(&format!(", repeats {}", rule));
+            }
+            if let Some(estimate) = task.estimate_minutes {
+                message.push_str(&format!(", estimated {}", models::format_minutes(estimate)));
+                match app.suggested_difficulty(estimate)? {
+                    Some(suggested) if suggested != task.difficulty => {
+                        message.push_str(&format!(" (your history suggests difficulty {})", suggested))
+                    }
+                    _ => {}
+                }
+            }
+            report_task(&task, json, message)
+        }
+        Command::List { all, tags, project, sort, reverse, then } => {
+            let mut tasks = if all {
+                app.db.get_all_tasks()?
+            } else {
+                app.db.get_pending_tasks()?
+            };
+            if !tags.is_empty() {
+                let wanted: Vec<String> = tags.iter().map(|t| t.trim_start_matches('#').to_lowercase()).collect();
+                tasks.retain(|task| task.tags.iter().any(|t| wanted.contains(t)));
+            }
+            if let Some(name) = project {
+                let project = find_project(app, &name)?;
+                tasks.retain(|task| task.project_id == Some(project.id));
+            }
+            let saved = app.sort_mode;
+            let order = SortMode {
+                key: sort.unwrap_or(saved.key),
+                reverse: if sort.is_some() { reverse } else { saved.reverse != reverse },
+                then: then.or(if sort.is_some() { None } else { saved.then }),
+            };
+            order.apply(&mut tasks, &app.sort_context());
+            list(app, &tasks, json)
+        }
+        Command::Search { words, limit } => {
+            let hits = app.db.search_tasks(&words.join(" "), limit)?;
+            search_results(&hits, json)
+        }
+        Command::Start { id } => {
+            let task = app.start_task(id)?;
+            report_task(&task, json, format!("Started #{} '{}'", task.id, task.title))
+        }
+        Command::Pause { id } => {
+            let task = app.pause_task(id)?;
+            report_task(&task, json, format!("Paused #{} '{}'", task.id, task.title))
+        }
+        Command::Done { id } => {
+            let task = app.complete_task(id)?;
+            let summary = task.points_breakdown.as_ref().map(|b| b.summary()).unwrap_or_default();
+            let mut message = format!(
+                "Completed #{} '{}': +{} points ({}), {} total",
+                task.id, task.title, task.points_earned, summary, app.user_stats.total_points
+            );
+            if let Some(next) = app.tasks.iter().find(|t| t.series_id.is_some() && t.series_id == task.series_id) {
+                message.push_str(&format!(
+                    "; next #{} due {}",
+                    next.id,
+                    next.due_date.map(|d| d.format("%a %Y-%m-%d").to_string()).unwrap_or_default()
+                ));
+            }
+            report_task(&task, json, message)
+        }
+        Command::Delete { id } => {
+            let task = app.delete_task(id)?;
+            report_task(&task, json, format!("Deleted #{} '{}'", task.id, task.title))
+        }
+        Command::Depends { id, prerequisites, clear } => {
+            let task = if clear || !prerequisites.is_empty() {
+                let mut prerequisites = prerequisites;
+                prerequisites.sort_unstable();
+                prerequisites.dedup();
+                app.set_dependencies(id, prerequisites)?
+            } else {
+                app.db.get_task(id)?.ok_or_else(|| anyhow!("Task #{} not found", id))?
+            };
+            let message = if task.depends_on.is_empty() {
+                format!("#{} '{}' has no prerequisites", task.id, task.title)
+            } else if task.is_blocked() {
+                format!(
+                    "#{} '{}' depends on {} (blocked by {})",
+                    task.id,
+                    task.title,
+                    models::format_task_ids(&task.depends_on),
+                    models::format_task_ids(&task.blocked_by)
+                )
+            } else {
+                format!("#{} '{}' depends on {} (all done)", task.id, task.title, models::format_task_ids(&task.depends_on))
+            };
+            report_task(&task, json, message)
+        }
+        Command::Due { id, when, clear } => {
+            let task = match (when, clear) {
+                (Some(when), _) => {
+                    let due = models::parse_due_date(&when, app.today()).map_err(|e| anyhow!(e))?;
+                    app.set_due_date(id, due)?
+                }
+                (None, true) => app.set_due_date(id, None)?,
+                (None, false) => app.db.get_task(id)?.ok_or_else(|| anyhow!("Task #{} not found", id))?,
+            };
+            let message = match task.due_date {
+                Some(due) => format!(
+                    "#{} '{}' is due {} ({})",
+                    task.id,
+                    task.title,
+                    due.format("%a %Y-%m-%d"),
+                    models::describe_due(due, app.today())
+                ),
+                None => format!("#{} '{}' has no due date", task.id, task.title),
+            };
+            report_task(&task, json, message)
+        }
+        Command::Repeat { id, rule, stop } => {
+            let task = match (rule, stop) {
+                (Some(rule), _) => {
+                    let current = app.db.get_task(id)?.ok_or_else(|| anyhow!("Task #{} not found", id))?;
+                    let anchor = current.due_date.unwrap_or(app.today());
+                    let rule = models::parse_recurrence(&rule, anchor).map_err(|e| anyhow!(e))?;
+                    app.set_recurrence(id, rule)?
+                }
+                (None, true) => app.set_recurrence(id, None)?,
+                (None, false) => app.db.get_task(id)?.ok_or_else(|| anyhow!("Task #{} not found", id))?,
+            };
+            let message = match &task.recurrence {
+                Some(rule) => format!("#{} '{}' repeats {}", task.id, task.title, rule),
+                None => format!("#{} '{}' does not repeat", task.id, task.title),
+            };
+            report_task(&task, json, message)
+        }
+        Command::Notes { id, text, clear } => {
+            let task = match (text, clear) {
+                (Some(text), _) => app.set_notes(id, read_text(text)?)?,
+                (None, true) => app.set_notes(id, String::new())?,
+                (None, false) => app.db.get_task(id)?.ok_or_else(|| anyhow!("Task #{} not found", id))?,
+            };
+            let message = if task.notes.is_empty() {
+                format!("#{} '{}' has no notes", task.id, task.title)
+            } else {
+                task.notes.clone()
+            };
+            report_task(&task, json, message)
+        }
+        Command::Estimate { id, time, clear } => {
+            let task = match (time, clear) {
+                (Some(time), _) => {
+                    let estimate = models::parse_estimate(&time).map_err(|e| anyhow!(e))?;
+                    app.set_estimate(id, estimate)?
+                }
+                (None, true) => app.set_estimate(id, None)?,
+                (None, false) => app.db.get_task(id)?.ok_or_else(|| anyhow!("Task #{} not found", id))?,
+            };
+            let message = match task.estimate_minutes {
+                Some(estimate) => {
+                    let actual = task.active_seconds_at(app.clock.now());
+                    format!(
+                        "#{} '{}' estimated {}, tracked {}h {}m",
+                        task.id,
+                        task.title,
+                        models::format_minutes(estimate),
+                        actual / 3600,
+                        (actual % 3600) / 60
+                    )
+                }
+                None => format!("#{} '{}' has no estimate", task.id, task.title),
+            };
+            report_task(&task, json, message)
+        }
+        Command::Stats { project: None } => stats(app, json),
+        Command::Stats { project: Some(name) } => {
+            let project = find_project(app, &name)?;
+            let stats = app.db.get_project_stats(Some(project.id))?;
+            project_stats(&[(project, stats)], json)
+        }
+        Command::Projects => {
+            let mut projects = Vec::new();
+            for project in app.db.get_projects()? {
+                let stats = app.db.get_project_stats(Some(project.id))?;
+                projects.push((project, stats));
+            }
+            project_stats(&projects, json)
+        }
+        Command::Calibration { project } => {
+            let scope = match project {
+                Some(name) => Some(find_project(app, &name)?.id),
+                None => None,
+            };
+            calibration(app, scope, json)
+        }
+        Command::Recompute { yes, dry_run } => recompute(app, yes, dry_run, json),
+    }
+}
+
+fn find_project(app: &App, name: &str) -> Result<Project> {
+    app.db
+        .find_project(name.trim())?
+        .ok_or_else(|| anyhow!("Project '{}' not found", name.trim()))
+}
+
+/// Command-line text, or all of stdin when it is `-`
+fn read_text(text: String) -> Result<String> {
+    if text != "-" {
+        return Ok(text);
+    }
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+    Ok(input)
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// The affected task as JSON, or a one-line confirmation
+fn report_task(task: &Task, json: bool, message: String) -> Result<()> {
+    if json {
+        return print_json(task);
+    }
+    println!("{}", message);
+    Ok(())
+}
+
+/// Tasks in tree order, subtasks indented under their parents
+fn list(app: &App, tasks: &[Task], json: bool) -> Result<()> {
+    let refs: Vec<&Task> = tasks.iter().collect();
+    let rows = models::build_tree(&refs, &HashSet::new());
+    if json {
+        return print_json(&rows.iter().map(|row| row.task).collect::<Vec<_>>());
+    }
+    if rows.is_empty() {
+        println!("No tasks.");
+        return Ok(());
+    }
+
+    let today = app.today();
+    println!("{:>4}  {:<11}  {:>4}  {:>6}  {:<12}  TITLE", "ID", "STATUS", "DIFF", "POINTS", "PROJECT");
+    for row in rows {
+        let task = row.task;
+        let indent = if row.depth > 0 {
+            format!("{}└ ", "  ".repeat(row.depth - 1))
+        } else {
+            String::new()
+        };
+        let mut suffix = app
+            .subtask_progress
+            .get(&task.id)
+            .map(|p| format!(" [{}/{}]", p.done, p.total))
+            .unwrap_or_default();
+        if task.is_blocked() {
+            suffix.push_str(&format!(" (blocked by {})", models::format_task_ids(&task.blocked_by)));
+        }
+        if let (Some(due), false) = (task.due_date, task.status == TaskStatus::Completed) {
+            suffix.push_str(&format!(" ({})", models::describe_due(due, today)));
+        }
+        if let (Some(rule), false) = (&task.recurrence, task.status == TaskStatus::Completed) {
+            suffix.push_str(&format!(" (↻ {})", rule));
+        }
+        if let Some(estimate) = task.estimate_minutes {
+            suffix.push_str(&format!(" (est {})", models::format_minutes(estimate)));
+        }
+        let status = if task.is_blocked() { "Blocked" } else { task.status.as_str() };
+        println!(
+            "{:>4}  {:<11}  {:>4}  {:>6}  {:<12}  {}{}{}",
+            task.id,
+            status,
+            task.difficulty,
+            app.rolled_up_points(task),
+            task.project.as_deref().unwrap_or("-"),
+            indent,
+            task.title_with_tags(),
+            suffix
+        );
+    }
+    Ok(())
+}
+
+fn search_results(hits: &[SearchHit], json: bool) -> Result<()> {
+    if json {
+        return print_json(&hits.iter().map(|hit| &hit.task).collect::<Vec<_>>());
+    }
+    if hits.is_empty() {
+        println!("No matching tasks");
+        return Ok(());
+    }
+
+    // Matched words are bold only when writing to a terminal
+    let color = io::stdout().is_terminal();
+    for hit in hits {
+        let task = &hit.task;
+        let date = task.completed_at.unwrap_or(task.created_at).format("%Y-%m-%d");
+        let tags: String = task.tags.iter().map(|t| format!(" #{}", t)).collect();
+        println!(
+            "{:>4}  {:<11}  {}  {}{}",
+            task.id,
+            task.status.as_str(),
+            date,
+            mark_matches(&hit.title, color),
+            tags
+        );
+        if let Some(notes) = &hit.notes {
+            println!("{:>31}{}", "", mark_matches(&notes.replace('\n', " "), color));
+        }
+    }
+    Ok(())
+}
+
+/// Search text with its match markers turned into bold yellow, or dropped
+fn mark_matches(marked: &str, color: bool) -> String {
+    models::match_segments(marked)
+        .into_iter()
+        .map(|(text, is_match)| match (is_match, color) {
+            (true, true) => format!("\x1b[1;33m{}\x1b[0m", text),
+            _ => text.to_string(),
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct ProjectReport<'a> {
+    #[serde(flatten)]
+    project: &'a Project,
+    #[serde(flatten)]
+    stats: &'a ProjectStats,
+}
+
+/// Completed tasks, points and focus time per project
+fn project_stats(projects: &[(Project, ProjectStats)], json: bool) -> Result<()> {
+    if json {
+        let reports: Vec<ProjectReport> = projects
+            .iter()
+            .map(|(project, stats)| ProjectReport { project, stats })
+            .collect();
+        return print_json(&reports);
+    }
+    if projects.is_empty() {
+        println!("No projects.");
+        return Ok(());
+    }
+
+    println!("{:<20}  {:>6}  {:>5}  FOCUS", "PROJECT", "POINTS", "TASKS");
+    for (project, stats) in projects {
+        println!(
+            "{:<20}  {:>6}  {:>5}  {}h {}m",
+            project.name,
+            stats.points_earned,
+            stats.tasks_completed,
+            stats.focus_time / 3600,
+            (stats.focus_time % 3600) / 60
+        );
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct StatsReport<'a> {
+    #[serde(flatten)]
+    stats: &'a UserStats,
+    strategy: &'a str,
+    points_by_reason: Vec<ReasonTotal>,
+    by_tag: Vec<TagStats>,
+    by_series: Vec<SeriesStats>,
+    estimates: Vec<EstimateAccuracy>,
+}
+
+#[derive(Serialize)]
+struct ReasonTotal {
+    reason: String,
+    points: i64,
+}
+
+fn stats(app: &App, json: bool) -> Result<()> {
+    let stats = &app.user_stats;
+    let by_reason = app.db.get_points_by_reason()?;
+    let by_tag = app.db.get_tag_stats()?;
+    let by_series = app.db.get_series_stats(app.today())?;
+    let estimates = models::estimate_accuracy_by_difficulty(&app.db.get_estimate_samples(None)?);
+
+    if json {
+        return print_json(&StatsReport {
+            stats,
+            strategy: app.strategy.kind().as_str(),
+            points_by_reason: by_reason
+                .iter()
+                .map(|(reason, points)| ReasonTotal {
+                    reason: format!("{:?}", reason),
+                    points: *points,
+                })
+                .collect(),
+            by_tag,
+            by_series,
+            estimates,
+        });
+    }
+
+    println!("Total points:    {}", stats.total_points);
+    println!("Tasks completed: {}", stats.tasks_completed);
+    println!("Current streak:  {} days", stats.current_streak);
+    println!("Longest streak:  {} days", stats.longest_streak);
+    println!(
+        "Focus time:      {}h {}m",
+        stats.total_focus_time / 3600,
+        (stats.total_focus_time % 3600) / 60
+    );
+    println!("Bonus pool:      {:.0}%", stats.streak_bonus_pool * 100.0);
+    println!("Strategy:        {}", app.strategy.kind().as_str());
+
+    if !by_reason.is_empty() {
+        println!();
+        println!("Points by source:");
+        for (reason, points) in &by_reason {
+            println!("  {:<18} {:>6}", reason.as_str(), points);
+        }
+    }
+
+    if !by_tag.is_empty() {
+        println!();
+        println!("Completed work by tag:");
+        for tag in &by_tag {
+            println!(
+                "  #{:<17} {:>6} pts  {:>3} tasks  {}h {}m",
+                tag.tag,
+                tag.points_earned,
+                tag.tasks_completed,
+                tag.focus_time / 3600,
+                (tag.focus_time % 3600) / 60
+            );
+        }
+    }
+
+    if !by_series.is_empty() {
+        println!();
+        println!("Recurring tasks:");
+        for series in &by_series {
+            println!(
+                "  {:<24} {:>4}  {:>7} done  {:>6} pts  {}{}",
+                series.title,
+                series.completion_rate.map_or_else(|| "new".to_string(), |r| format!("{:.0}%", r * 100.0)),
+                format!("{}/{}", series.instances_completed, series.occurrences),
+                series.points_earned,
+                series.rule,
+                if series.active { "" } else { " (stopped)" }
+            );
+        }
+    }
+
+    if !estimates.is_empty() {
+        println!();
+        println!("Estimation error by difficulty (positive = took longer):");
+        for accuracy in &estimates {
+            println!(
+                "  difficulty {:>2}  {:>+5.0}% avg  ±{:.0}% off  {:>3} tasks",
+                accuracy.difficulty,
+                accuracy.mean_error * 100.0,
+                accuracy.mean_abs_error * 100.0,
+                accuracy.tasks
+            );
+        }
+    }
+    Ok(())
+}
+
+fn calibration(app: &App, project: Option<i64>, json: bool) -> Result<()> {
+    let levels = models::calibrate_difficulties(&app.db.get_completion_times(project)?);
+    if json {
+        return print_json(&levels);
+    }
+    if levels.is_empty() {
+        println!("No completed tasks with tracked time yet");
+        return Ok(());
+    }
+
+    let minutes = |seconds: f64| models::format_minutes((seconds / 60.0).round() as i64);
+    println!("DIFF  TASKS   MEDIAN   MIDDLE HALF        AVERAGE");
+    for level in &levels {
+        let mut flags = Vec::new();
+        if let Some(lower) = level.not_slower_than {
+            flags.push(format!("not slower than difficulty {}", lower));
+        }
+        if !level.overlaps_with.is_empty() {
+            let others = level.overlaps_with.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+            flags.push(format!("overlaps {}", others.join(", ")));
+        }
+        if !level.is_reliable() {
+            flags.push("too few tasks to compare".to_string());
+        }
+        let line = format!(
+            "{:>4}  {:>5}  {:>7}   {:<17}  {:>7}  {}",
+            level.difficulty,
+            level.tasks,
+            minutes(level.median_seconds),
+            format!("{} – {}", minutes(level.p25_seconds), minutes(level.p75_seconds)),
+            minutes(level.mean_seconds),
+            flags.join("; ")
+        );
+        println!("{}", line.trim_end());
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct RecomputeReport<'a> {
+    strategy: &'a str,
+    applied: bool,
+    before: &'a UserStats,
+    after: &'a UserStats,
+    changed_tasks: Vec<TaskChange<'a>>,
+}
+
+#[derive(Serialize)]
+struct TaskChange<'a> {
+    id: i64,
+    title: &'a str,
+    old_points: i32,
+    new_points: i32,
+}
+
+fn recompute(app: &mut App, yes: bool, dry_run: bool, json: bool) -> Result<()> {
+    if json && !yes && !dry_run {
+        bail!("recompute --json needs --yes or --dry-run, since it cannot ask for confirmation");
+    }
+
+    let plan = app.plan_recompute()?;
+    let apply = !dry_run && !plan.is_unchanged();
+
+    if json {
+        if apply {
+            app.apply_recompute(&plan)?;
+        }
+        return print_json(&recompute_report(app, &plan, apply));
+    }
+
+    println!(
+        "Replaying {} completed tasks with the '{}' strategy",
+        plan.tasks.len(),
+        app.strategy.kind().as_str()
+    );
+
+    if plan.is_unchanged() {
+        println!("Points and stats already match task history.");
+        return Ok(());
+    }
+
+    println!();
+    for line in plan.diff_lines() {
+        println!("{}", line);
+    }
+    println!();
+
+    if dry_run {
+        println!("Dry run: nothing was changed.");
+        return Ok(());
+    }
+
+    if !yes && !confirm("Apply these changes?")? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    app.apply_recompute(&plan)?;
+    println!("Recompute applied.");
+    Ok(())
+}
+
+fn recompute_report<'a>(app: &'a App, plan: &'a Recompute, applied: bool) -> RecomputeReport<'a> {
+    RecomputeReport {
+        strategy: app.strategy.kind().as_str(),
+        applied,
+        before: &plan.before,
+        after: &plan.after,
+        changed_tasks: plan
+            .changed_tasks()
+            .map(|r| TaskChange {
+                id: r.task.id,
+                title: &r.task.title,
+                old_points: r.old_points,
+                new_points: r.task.points_earned,
+            })
+            .collect(),
+    }
+}
+
+fn confirm(question: &str) -> Result<bool> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::cell::Cell;
+
+/// Source of "now" for everything that records or measures time. The app
+/// asks the clock once per operation and passes the timestamp down, so
+/// scoring and the database never read the system time themselves.
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that only moves when told to
+pub struct FakeClock {
+    now: Cell<DateTime<Utc>>,
+}
+
+impl FakeClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self { now: Cell::new(now) }
+    }
+
+    #[cfg(test)]
+    pub fn set(&self, now: DateTime<Utc>) {
+        self.now.set(now);
+    }
+
+    #[cfg(test)]
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.now.get()
+    }
+}
+
+/// The system clock, or a fake one frozen at `TASKQUEST_NOW` (RFC 3339)
+/// for reproducible runs
+pub fn from_env() -> Result<Box<dyn Clock>> {
+    match std::env::var("TASKQUEST_NOW") {
+        Ok(value) if !value.is_empty() => {
+            let now = DateTime::parse_from_rfc3339(&value)
+                .with_context(|| format!("TASKQUEST_NOW is not an RFC 3339 timestamp: {}", value))?;
+            Ok(Box::new(FakeClock::new(now.with_timezone(&Utc))))
+        }
+        _ => Ok(Box::new(SystemClock)),
+    }
+}
+
+/// Where one day ends and the next begins: midnight (or a later cutoff for
+/// night owls) in the user's timezone. Streaks and daily stats both count
+/// days this way.
+#[derive(Debug, Clone, Copy)]
+pub struct DayBoundary {
+    timezone: Option<Tz>, // None = system timezone
+    starts_at: NaiveTime,
+}
+
+impl Default for DayBoundary {
+    fn default() -> Self {
+        Self::new(None, NaiveTime::MIN)
+    }
+}
+
+impl DayBoundary {
+    pub fn new(timezone: Option<Tz>, starts_at: NaiveTime) -> Self {
+        Self { timezone, starts_at }
+    }
+
+    /// The day `at` counts towards
+    pub fn day_of(&self, at: DateTime<Utc>) -> NaiveDate {
+        let local = match self.timezone {
+            Some(tz) => at.with_timezone(&tz).naive_local(),
+            None => at.with_timezone(&Local).naive_local(),
+        };
+        (local - (self.starts_at - NaiveTime::MIN)).date()
+    }
+
+    /// First instant of `day`
+    pub fn start_of(&self, day: NaiveDate) -> DateTime<Utc> {
+        let naive = day.and_time(self.starts_at);
+        match self.timezone {
+            Some(tz) => resolve(&tz, naive),
+            None => resolve(&Local, naive),
+        }
+    }
+
+    /// Whole days between the days two instants count towards
+    pub fn days_between(&self, earlier: DateTime<Utc>, later: DateTime<Utc>) -> i64 {
+        (self.day_of(later) - self.day_of(earlier)).num_days()
+    }
+
+    pub fn describe(&self) -> String {
+        let zone = self
+            .timezone
+            .map(|tz| tz.name().to_string())
+            .unwrap_or_else(|| "system timezone".to_string());
+        format!("{}, days start at {}", zone, self.starts_at.format("%H:%M"))
+    }
+}
+
+/// Local wall-clock time to an instant. Times skipped by a DST change move
+/// forward to the first valid one; repeated times take the earlier.
+fn resolve<Z: TimeZone>(tz: &Z, naive: NaiveDateTime) -> DateTime<Utc> {
+    let mut candidate = naive;
+    for _ in 0..4 {
+        match tz.from_local_datetime(&candidate) {
+            LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => return dt.with_timezone(&Utc),
+            LocalResult::None => candidate += Duration::minutes(30),
+        }
+    }
+    naive.and_utc()
+}
@@ -0,0 +1,222 @@
+use crate::clock::DayBoundary;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{NaiveTime, Timelike};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+const DEFAULT_PROFILE: &str = "default";
+
+/// User configuration loaded from `~/.config/taskquest/config.toml`.
+/// Every key is optional; anything left out keeps its default.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub profile: Option<String>, // active profile; TASKQUEST_PROFILE overrides
+    pub scoring: ScoringRules,
+    pub day: DayConfig,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// How days are counted for streaks and daily stats
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DayConfig {
+    pub timezone: Option<String>,  // IANA name, e.g. "Europe/Berlin"; system timezone when unset
+    pub starts_at: Option<String>, // "HH:MM" before noon; work before this counts for the previous day
+}
+
+impl DayConfig {
+    pub fn boundary(&self) -> Result<DayBoundary> {
+        let timezone = match &self.timezone {
+            Some(name) => Some(
+                name.parse::<Tz>()
+                    .map_err(|_| anyhow!("day.timezone '{}' is not a known IANA timezone", name))?,
+            ),
+            None => None,
+        };
+
+        let starts_at = match &self.starts_at {
+            Some(value) => {
+                let time = NaiveTime::parse_from_str(value, "%H:%M")
+                    .with_context(|| format!("day.starts_at must be HH:MM (got '{}')", value))?;
+                if time.hour() >= 12 {
+                    bail!("day.starts_at must be before 12:00 (got {})", value);
+                }
+                time
+            }
+            None => NaiveTime::MIN,
+        };
+
+        Ok(DayBoundary::new(timezone, starts_at))
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub strategy: StrategyKind,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StrategyKind {
+    #[default]
+    Classic,
+    DifficultyOnly,
+    EstimateAccuracy,
+}
+
+impl StrategyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StrategyKind::Classic => "classic",
+            StrategyKind::DifficultyOnly => "difficulty-only",
+            StrategyKind::EstimateAccuracy => "estimate-accuracy",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            StrategyKind::Classic => "difficulty, speed, streak and focus all count",
+            StrategyKind::DifficultyOnly => "flat points per difficulty, no bonuses or milestones",
+            StrategyKind::EstimateAccuracy => "rewards finishing close to the task's estimate, or the usual time for its difficulty when it has none",
+        }
+    }
+}
+
+/// Tunable constants of the scoring game
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoringRules {
+    pub base_points_multiplier: i32, // base points = difficulty × this
+    pub milestone_thresholds: Vec<i32>, // completed-task counts that award a bonus
+    pub milestone_bonus: i32,        // scaled by tasks_completed / 10
+    pub streak_step: f32,            // multiplier gained per streak day
+    pub streak_cap: f32,             // maximum streak bonus (0.5 = +50%)
+    pub short_gap_decay: f32,        // bonus pool factor after missing 2-3 days
+    pub long_gap_decay: f32,         // bonus pool factor after missing 4+ days
+    pub on_time_bonus: f32,          // share of base points for finishing by the due date; 0 = off
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        Self {
+            base_points_multiplier: 10,
+            milestone_thresholds: vec![10, 25, 50, 100, 250, 500],
+            milestone_bonus: 100,
+            streak_step: 0.05,
+            streak_cap: 0.5,
+            short_gap_decay: 0.7,
+            long_gap_decay: 0.3,
+            on_time_bonus: 0.0,
+        }
+    }
+}
+
+impl ScoringRules {
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=1000).contains(&self.base_points_multiplier) {
+            bail!(
+                "scoring.base_points_multiplier must be between 1 and 1000 (got {})",
+                self.base_points_multiplier
+            );
+        }
+        if self.milestone_thresholds.iter().any(|&t| t <= 0) {
+            bail!("scoring.milestone_thresholds must all be positive");
+        }
+        if self.milestone_thresholds.windows(2).any(|w| w[0] >= w[1]) {
+            bail!(
+                "scoring.milestone_thresholds must be strictly increasing (got {:?})",
+                self.milestone_thresholds
+            );
+        }
+        if self.milestone_bonus < 0 {
+            bail!("scoring.milestone_bonus cannot be negative (got {})", self.milestone_bonus);
+        }
+        if !(self.streak_step > 0.0 && self.streak_step <= 1.0) {
+            bail!("scoring.streak_step must be in (0, 1] (got {})", self.streak_step);
+        }
+        if !(0.0..=5.0).contains(&self.streak_cap) {
+            bail!("scoring.streak_cap must be between 0 and 5 (got {})", self.streak_cap);
+        }
+        for (name, value) in [
+            ("short_gap_decay", self.short_gap_decay),
+            ("long_gap_decay", self.long_gap_decay),
+            ("on_time_bonus", self.on_time_bonus),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                bail!("scoring.{} must be between 0 and 1 (got {})", name, value);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Load and validate the config file, falling back to defaults when it
+    /// does not exist
+    pub fn load() -> Result<Self> {
+        let path = Self::get_config_path()?;
+
+        let config = if path.exists() {
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let config: Config = toml::from_str(&contents)
+                .with_context(|| format!("Invalid config file {}", path.display()))?;
+            config
+                .scoring
+                .validate()
+                .with_context(|| format!("Invalid scoring rules in {}", path.display()))?;
+            config
+                .day
+                .boundary()
+                .with_context(|| format!("Invalid day settings in {}", path.display()))?;
+            config
+        } else {
+            Self::default()
+        };
+
+        config
+            .active_profile()
+            .with_context(|| format!("Invalid profile selection for {}", path.display()))?;
+
+        Ok(config)
+    }
+
+    pub fn active_profile_name(&self) -> String {
+        std::env::var("TASKQUEST_PROFILE")
+            .ok()
+            .filter(|p| !p.is_empty())
+            .or_else(|| self.profile.clone())
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+    }
+
+    /// The selected profile. "default" works without being declared.
+    pub fn active_profile(&self) -> Result<Profile> {
+        let name = self.active_profile_name();
+        match self.profiles.get(&name) {
+            Some(profile) => Ok(profile.clone()),
+            None if name == DEFAULT_PROFILE => Ok(Profile::default()),
+            None => bail!(
+                "profile '{}' is not defined (known profiles: {})",
+                name,
+                self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+
+    pub fn get_config_path() -> Result<PathBuf> {
+        let home = std::env::var("HOME")
+            .or_else(|_| std::env::var("USERPROFILE"))
+            .context("Could not determine home directory")?;
+
+        let mut path = PathBuf::from(home);
+        path.push(".config");
+        path.push("taskquest");
+        path.push("config.toml");
+
+        Ok(path)
+    }
+}
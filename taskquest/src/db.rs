@@ -1,15 +1,29 @@
-use crate::models::{DailyStats, Task, TaskStatus, UserStats};
-use anyhow::{Context, Result};
-use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
-use rusqlite::{params, Connection};
+use crate::clock::DayBoundary;
+use crate::migrations;
+use crate::models::{
+    DailyStats, EstimateSample, LedgerEntry, MATCH_START, PointGrant, PointReason, PointsBreakdown, Project, ProjectStats,
+    NewTask, Recurrence, SearchHit, SeriesStats, SubtaskProgress, TagStats, Task, TaskEvent, TaskEventKind, TaskStatus, TimeEntry, UserStats,
+};
+use crate::recompute::Recompute;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::types::{Type, ValueRef};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Row};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::PathBuf;
+use std::time::Duration;
+
+const TASK_COLUMNS: &str = "id, title, difficulty, status, created_at, started_at, completed_at,
+     points_earned, points_breakdown, project_id,
+     (SELECT name FROM projects WHERE projects.id = tasks.project_id), parent_id, due_date, series_id,
+     (SELECT rule FROM series WHERE series.id = tasks.series_id AND series.active), notes, estimate_minutes";
 
 pub struct Database {
     conn: Connection,
 }
 
 impl Database {
-    pub fn new() -> Result<Self> {
+    pub fn new(now: DateTime<Utc>) -> Result<Self> {
         let db_path = Self::get_db_path()?;
 
         // Create parent directory if it doesn't exist
@@ -17,12 +31,46 @@ impl Database {
             std::fs::create_dir_all(parent)?;
         }
 
-        let conn = Connection::open(&db_path)
+        let mut conn = Connection::open(&db_path)
             .context("Failed to open database")?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+        migrations::migrate(&mut conn, &db_path, now)?;
+
+        Ok(Self { conn })
+    }
+
+    /// Open the existing database for reading only, without migrating.
+    /// Used by callers that poll frequently and must never block the TUI.
+    /// None if the database has not been created yet.
+    pub fn open_read_only() -> Result<Option<Self>> {
+        let db_path = Self::get_db_path()?;
+        if !db_path.exists() {
+            return Ok(None);
+        }
+        let conn = Connection::open_with_flags(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open {}", db_path.display()))?;
+        conn.busy_timeout(Duration::from_millis(250))?;
+
+        let version = migrations::current_version(&conn)?;
+        if version != migrations::latest_version() {
+            bail!(
+                "Database {} is at schema version {} (expected {}); start TaskQuest once to upgrade it",
+                db_path.display(),
+                version,
+                migrations::latest_version()
+            );
+        }
+
+        Ok(Some(Self { conn }))
+    }
 
-        let mut db = Self { conn };
-        db.init_schema()?;
-        Ok(db)
+    /// A fresh, fully migrated database that lives only in memory
+    #[cfg(test)]
+    pub fn open_in_memory(now: DateTime<Utc>) -> Result<Self> {
+        let mut conn = Connection::open_in_memory()?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+        migrations::migrate(&mut conn, std::path::Path::new(":memory:"), now)?;
+        Ok(Self { conn })
     }
 
     fn get_db_path() -> Result<PathBuf> {
@@ -39,137 +87,200 @@ impl Database {
         Ok(path)
     }
 
-    fn init_schema(&mut self) -> Result<()> {
-        self.conn.execute_batch(
-            "
-            CREATE TABLE IF NOT EXISTS tasks (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                title TEXT NOT NULL,
-                difficulty INTEGER NOT NULL CHECK(difficulty >= 1 AND difficulty <= 10),
-                status TEXT NOT NULL,
-                created_at TEXT NOT NULL,
-                started_at TEXT,
-                completed_at TEXT,
-                paused_at TEXT,
-                total_pause_duration INTEGER DEFAULT 0,
-                points_earned INTEGER DEFAULT 0
-            );
-
-            CREATE TABLE IF NOT EXISTS user_stats (
-                id INTEGER PRIMARY KEY CHECK(id = 1),
-                total_points INTEGER DEFAULT 0,
-                current_streak INTEGER DEFAULT 0,
-                longest_streak INTEGER DEFAULT 0,
-                last_completion_date TEXT,
-                tasks_completed INTEGER DEFAULT 0,
-                total_focus_time INTEGER DEFAULT 0,
-                streak_bonus_pool REAL DEFAULT 1.0
-            );
+    // Transactions
+    /// Start a transaction spanning several calls. Methods that group their
+    /// own writes use savepoints, so they nest inside it.
+    pub fn begin(&mut self) -> Result<()> {
+        self.conn.execute_batch("BEGIN")?;
+        Ok(())
+    }
 
-            INSERT OR IGNORE INTO user_stats (id) VALUES (1);
+    pub fn commit(&mut self) -> Result<()> {
+        self.conn.execute_batch("COMMIT")?;
+        Ok(())
+    }
 
-            CREATE INDEX IF NOT EXISTS idx_tasks_status ON tasks(status);
-            CREATE INDEX IF NOT EXISTS idx_tasks_completed_at ON tasks(completed_at);
-            ",
-        )?;
+    /// Undo everything since `begin`. A failed statement may already have
+    /// ended the transaction, in which case there is nothing left to do.
+    pub fn rollback(&mut self) -> Result<()> {
+        if !self.conn.is_autocommit() {
+            self.conn.execute_batch("ROLLBACK")?;
+        }
         Ok(())
     }
 
     // Task operations
-    pub fn create_task(&mut self, title: String, difficulty: i32) -> Result<Task> {
-        let now = Utc::now();
-        let now_str = now.to_rfc3339();
-
-        self.conn.execute(
-            "INSERT INTO tasks (title, difficulty, status, created_at) VALUES (?1, ?2, ?3, ?4)",
-            params![title, difficulty, "Pending", now_str],
+    pub fn create_task(&mut self, new: &NewTask, created_at: DateTime<Utc>) -> Result<Task> {
+        let tx = self.conn.savepoint()?;
+        let series_id = match (&new.recurrence, new.series_id) {
+            (_, Some(series_id)) => Some(series_id),
+            (Some(rule), None) => {
+                tx.execute(
+                    "INSERT INTO series (rule, created_at) VALUES (?1, ?2)",
+                    params![rule.to_string(), created_at.to_rfc3339()],
+                )?;
+                Some(tx.last_insert_rowid())
+            }
+            (None, None) => None,
+        };
+        tx.execute(
+            "INSERT INTO tasks (title, difficulty, status, created_at, project_id, parent_id, due_date,
+                                series_id, notes, estimate_minutes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                new.title,
+                new.difficulty,
+                "Pending",
+                created_at.to_rfc3339(),
+                new.project.as_ref().map(|p| p.id),
+                new.parent_id,
+                new.due_date,
+                series_id,
+                new.notes,
+                new.estimate_minutes,
+            ],
         )?;
+        let id = tx.last_insert_rowid();
+        Self::write_tags(&tx, id, &new.tags)?;
+        Self::write_dependencies(&tx, id, &new.depends_on)?;
+        tx.commit()?;
 
-        let id = self.conn.last_insert_rowid();
+        self.get_task(id)?
+            .with_context(|| format!("Task #{} disappeared after being created", id))
+    }
 
-        Ok(Task {
-            id,
-            title,
-            difficulty,
-            status: TaskStatus::Pending,
-            created_at: now,
-            started_at: None,
-            completed_at: None,
-            paused_at: None,
-            total_pause_duration: 0,
-            points_earned: 0,
-        })
+    pub fn get_task(&self, task_id: i64) -> Result<Option<Task>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM tasks WHERE id = ?1",
+            TASK_COLUMNS
+        ))?;
+
+        let mut tasks = stmt
+            .query_map(params![task_id], Self::row_to_task)?
+            .collect::<Result<Vec<_>, _>>()?;
+        self.attach_related(&mut tasks)?;
+
+        Ok(tasks.pop())
     }
 
     pub fn get_all_tasks(&self) -> Result<Vec<Task>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, title, difficulty, status, created_at, started_at, completed_at,
-                    paused_at, total_pause_duration, points_earned
-             FROM tasks ORDER BY created_at DESC"
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM tasks ORDER BY created_at DESC",
+            TASK_COLUMNS
+        ))?;
 
-        let tasks = stmt
-            .query_map([], |row| {
-                Ok(Task {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    difficulty: row.get(2)?,
-                    status: Self::parse_status(row.get::<_, String>(3)?),
-                    created_at: Self::parse_datetime(row.get(4)?),
-                    started_at: row.get::<_, Option<String>>(5)?.map(|s| Self::parse_datetime(s)),
-                    completed_at: row.get::<_, Option<String>>(6)?.map(|s| Self::parse_datetime(s)),
-                    paused_at: row.get::<_, Option<String>>(7)?.map(|s| Self::parse_datetime(s)),
-                    total_pause_duration: row.get(8)?,
-                    points_earned: row.get(9)?,
-                })
-            })?
+        let mut tasks = stmt
+            .query_map([], Self::row_to_task)?
             .collect::<Result<Vec<_>, _>>()?;
+        self.attach_related(&mut tasks)?;
 
         Ok(tasks)
     }
 
     pub fn get_pending_tasks(&self) -> Result<Vec<Task>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, title, difficulty, status, created_at, started_at, completed_at,
-                    paused_at, total_pause_duration, points_earned
-             FROM tasks WHERE status IN ('Pending', 'InProgress', 'Paused')
-             ORDER BY created_at DESC"
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM tasks WHERE status IN ('Pending', 'InProgress', 'Paused')
+             ORDER BY created_at DESC",
+            TASK_COLUMNS
+        ))?;
 
-        let tasks = stmt
-            .query_map([], |row| {
-                Ok(Task {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    difficulty: row.get(2)?,
-                    status: Self::parse_status(row.get::<_, String>(3)?),
-                    created_at: Self::parse_datetime(row.get(4)?),
-                    started_at: row.get::<_, Option<String>>(5)?.map(|s| Self::parse_datetime(s)),
-                    completed_at: row.get::<_, Option<String>>(6)?.map(|s| Self::parse_datetime(s)),
-                    paused_at: row.get::<_, Option<String>>(7)?.map(|s| Self::parse_datetime(s)),
-                    total_pause_duration: row.get(8)?,
-                    points_earned: row.get(9)?,
-                })
-            })?
+        let mut tasks = stmt
+            .query_map([], Self::row_to_task)?
             .collect::<Result<Vec<_>, _>>()?;
+        self.attach_related(&mut tasks)?;
 
         Ok(tasks)
     }
 
+    /// The most recently started task that is currently running
+    pub fn get_active_task(&self) -> Result<Option<Task>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM tasks WHERE status = 'InProgress'
+             ORDER BY started_at DESC LIMIT 1",
+            TASK_COLUMNS
+        ))?;
+
+        let mut tasks = stmt
+            .query_map([], Self::row_to_task)?
+            .collect::<Result<Vec<_>, _>>()?;
+        self.attach_related(&mut tasks)?;
+
+        Ok(tasks.pop())
+    }
+
+    /// Tasks whose title or notes contain every word of `query` (as a
+    /// prefix, so results can update while typing), best matches first
+    pub fn search_tasks(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let Some(fts_query) = Self::fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        // Titles weigh ten times as much as notes
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}, m.title_marked, m.notes_marked FROM tasks
+             JOIN (
+                 SELECT rowid AS task_id,
+                        highlight(tasks_fts, 0, char(1), char(2)) AS title_marked,
+                        snippet(tasks_fts, 1, char(1), char(2), '…', 12) AS notes_marked,
+                        bm25(tasks_fts, 10.0, 1.0) AS score
+                 FROM tasks_fts
+                 WHERE tasks_fts MATCH ?1
+             ) m ON m.task_id = tasks.id
+             ORDER BY m.score, tasks.id DESC
+             LIMIT ?2",
+            TASK_COLUMNS
+        ))?;
+
+        let rows = stmt
+            .query_map(params![fts_query, limit as i64], |row| {
+                Ok((Self::row_to_task(row)?, row.get::<_, String>(17)?, row.get::<_, String>(18)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (mut tasks, marked): (Vec<Task>, Vec<(String, String)>) =
+            rows.into_iter().map(|(task, title, notes)| (task, (title, notes))).unzip();
+        self.attach_related(&mut tasks)?;
+
+        Ok(tasks
+            .into_iter()
+            .zip(marked)
+            .map(|(task, (title, notes))| SearchHit {
+                task,
+                title,
+                notes: notes.contains(MATCH_START).then_some(notes),
+            })
+            .collect())
+    }
+
+    /// Turn what the user typed into an FTS5 query: every word quoted (so
+    /// FTS operators and punctuation are taken literally) and prefix-matched
+    fn fts_query(input: &str) -> Option<String> {
+        let terms: Vec<String> = input
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| format!("\"{}\"*", word))
+            .collect();
+        (!terms.is_empty()).then(|| terms.join(" "))
+    }
+
     pub fn update_task(&mut self, task: &Task) -> Result<()> {
         self.conn.execute(
             "UPDATE tasks SET title = ?1, difficulty = ?2, status = ?3, started_at = ?4,
-                    completed_at = ?5, paused_at = ?6, total_pause_duration = ?7, points_earned = ?8
-             WHERE id = ?9",
+                    completed_at = ?5, points_earned = ?6, points_breakdown = ?7, project_id = ?8,
+                    due_date = ?9, series_id = ?10, notes = ?11, estimate_minutes = ?12
+             WHERE id = ?13",
             params![
                 task.title,
                 task.difficulty,
                 Self::status_to_string(&task.status),
                 task.started_at.map(|dt| dt.to_rfc3339()),
                 task.completed_at.map(|dt| dt.to_rfc3339()),
-                task.paused_at.map(|dt| dt.to_rfc3339()),
-                task.total_pause_duration,
                 task.points_earned,
+                Self::breakdown_to_json(&task.points_breakdown)?,
+                task.project_id,
+                task.due_date,
+                task.series_id,
+                task.notes,
+                task.estimate_minutes,
                 task.id,
             ],
         )?;
@@ -181,10 +292,524 @@ impl Database {
         Ok(())
     }
 
+    /// Put a task row, its time entries and its ledger rows back to a
+    /// snapshot, or remove the task entirely when `task` is None. Used by
+    /// undo/redo.
+    pub fn restore_task(
+        &mut self,
+        task_id: i64,
+        task: Option<&Task>,
+        ledger: &[LedgerEntry],
+    ) -> Result<()> {
+        let tx = self.conn.savepoint()?;
+
+        match task {
+            Some(task) => {
+                tx.execute(
+                    "INSERT INTO tasks (id, title, difficulty, status, created_at, started_at,
+                                        completed_at, points_earned, points_breakdown, project_id,
+                                        parent_id, due_date, series_id, notes, estimate_minutes)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+                     ON CONFLICT(id) DO UPDATE SET
+                        title = excluded.title, difficulty = excluded.difficulty,
+                        status = excluded.status, created_at = excluded.created_at,
+                        started_at = excluded.started_at, completed_at = excluded.completed_at,
+                        points_earned = excluded.points_earned,
+                        points_breakdown = excluded.points_breakdown,
+                        project_id = excluded.project_id, parent_id = excluded.parent_id,
+                        due_date = excluded.due_date, series_id = excluded.series_id,
+                        notes = excluded.notes, estimate_minutes = excluded.estimate_minutes",
+                    params![
+                        task.id,
+                        task.title,
+                        task.difficulty,
+                        Self::status_to_string(&task.status),
+                        task.created_at.to_rfc3339(),
+                        task.started_at.map(|dt| dt.to_rfc3339()),
+                        task.completed_at.map(|dt| dt.to_rfc3339()),
+                        task.points_earned,
+                        Self::breakdown_to_json(&task.points_breakdown)?,
+                        task.project_id,
+                        task.parent_id,
+                        task.due_date,
+                        task.series_id,
+                        task.notes,
+                        task.estimate_minutes,
+                    ],
+                )?;
+                if let Some(series_id) = task.series_id {
+                    Self::write_series_rule(&tx, series_id, task.recurrence.as_ref())?;
+                }
+
+                Self::write_tags(&tx, task_id, &task.tags)?;
+                Self::write_dependencies(&tx, task_id, &task.depends_on)?;
+
+                tx.execute("DELETE FROM time_entries WHERE task_id = ?1", params![task_id])?;
+                for entry in &task.time_entries {
+                    tx.execute(
+                        "INSERT INTO time_entries (id, task_id, started_at, ended_at) VALUES (?1, ?2, ?3, ?4)",
+                        params![
+                            entry.id,
+                            entry.task_id,
+                            entry.started_at.to_rfc3339(),
+                            entry.ended_at.map(|dt| dt.to_rfc3339()),
+                        ],
+                    )?;
+                }
+            }
+            None => {
+                tx.execute("DELETE FROM tasks WHERE id = ?1", params![task_id])?;
+            }
+        }
+
+        tx.execute("DELETE FROM points_ledger WHERE task_id = ?1", params![task_id])?;
+        for entry in ledger {
+            tx.execute(
+                "INSERT INTO points_ledger (id, task_id, reason, points, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    entry.id,
+                    entry.task_id,
+                    Self::reason_to_string(&entry.reason),
+                    entry.points,
+                    entry.created_at.to_rfc3339(),
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    // Time entry operations
+    pub fn start_time_entry(&mut self, task_id: i64, at: DateTime<Utc>) -> Result<TimeEntry> {
+        self.conn.execute(
+            "INSERT INTO time_entries (task_id, started_at) VALUES (?1, ?2)",
+            params![task_id, at.to_rfc3339()],
+        )?;
+
+        Ok(TimeEntry {
+            id: self.conn.last_insert_rowid(),
+            task_id,
+            started_at: at,
+            ended_at: None,
+        })
+    }
+
+    /// Close the task's running interval, if any
+    pub fn stop_time_entry(&mut self, task_id: i64, at: DateTime<Utc>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE time_entries SET ended_at = ?1 WHERE task_id = ?2 AND ended_at IS NULL",
+            params![at.to_rfc3339(), task_id],
+        )?;
+        Ok(())
+    }
+
+    // Event log operations
+    pub fn record_event(
+        &mut self,
+        task_id: i64,
+        kind: TaskEventKind,
+        at: DateTime<Utc>,
+        detail: Option<String>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO task_events (task_id, kind, occurred_at, detail) VALUES (?1, ?2, ?3, ?4)",
+            params![task_id, kind.as_str(), at.to_rfc3339(), detail],
+        )?;
+        Ok(())
+    }
+
+    /// Full history of a task, oldest first
+    pub fn get_task_events(&self, task_id: i64) -> Result<Vec<TaskEvent>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, task_id, kind, occurred_at, detail
+             FROM task_events WHERE task_id = ?1
+             ORDER BY occurred_at, id"
+        )?;
+
+        let events = stmt
+            .query_map(params![task_id], |row| {
+                Ok(TaskEvent {
+                    id: row.get(0)?,
+                    task_id: row.get(1)?,
+                    kind: Self::parse_event_kind(row.get::<_, String>(2)?),
+                    occurred_at: Self::get_datetime(row, 3)?,
+                    detail: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(events)
+    }
+
+    /// Fill in each task's time entries, tags and dependencies
+    fn attach_related(&self, tasks: &mut [Task]) -> Result<()> {
+        let mut entries = self.conn.prepare_cached(
+            "SELECT id, task_id, started_at, ended_at
+             FROM time_entries WHERE task_id = ?1
+             ORDER BY started_at"
+        )?;
+        let mut tags = self.conn.prepare_cached(
+            "SELECT g.name FROM task_tags tt JOIN tags g ON g.id = tt.tag_id
+             WHERE tt.task_id = ?1
+             ORDER BY g.name"
+        )?;
+        let mut dependencies = self.conn.prepare_cached(
+            "SELECT d.depends_on_id, t.status = 'Completed'
+             FROM task_dependencies d JOIN tasks t ON t.id = d.depends_on_id
+             WHERE d.task_id = ?1
+             ORDER BY d.depends_on_id"
+        )?;
+
+        for task in tasks.iter_mut() {
+            task.time_entries = entries
+                .query_map(params![task.id], Self::row_to_time_entry)?
+                .collect::<Result<Vec<_>, _>>()?;
+            task.tags = tags
+                .query_map(params![task.id], |row| row.get(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let prerequisites = dependencies
+                .query_map(params![task.id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, bool>(1)?)))?
+                .collect::<Result<Vec<_>, _>>()?;
+            task.depends_on = prerequisites.iter().map(|(id, _)| *id).collect();
+            task.blocked_by = prerequisites
+                .iter()
+                .filter(|(_, completed)| !completed)
+                .map(|(id, _)| *id)
+                .collect();
+        }
+
+        Ok(())
+    }
+
+    // Subtasks
+    /// Progress of every task that has subtasks, keyed by task id. Counts
+    /// subtasks at any depth, completed or not.
+    pub fn get_subtask_progress(&self) -> Result<HashMap<i64, SubtaskProgress>> {
+        let mut stmt = self.conn.prepare(
+            "WITH RECURSIVE below(root, id) AS (
+                 SELECT parent_id, id FROM tasks WHERE parent_id IS NOT NULL
+                 UNION ALL
+                 SELECT below.root, t.id FROM tasks t JOIN below ON t.parent_id = below.id
+             )
+             SELECT below.root,
+                    SUM(t.status = 'Completed'),
+                    COUNT(*),
+                    SUM(CASE WHEN t.status = 'Completed' THEN t.points_earned ELSE 0 END)
+             FROM below JOIN tasks t ON t.id = below.id
+             GROUP BY below.root"
+        )?;
+
+        let progress = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    SubtaskProgress {
+                        done: row.get(1)?,
+                        total: row.get(2)?,
+                        points: row.get(3)?,
+                    },
+                ))
+            })?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(progress)
+    }
+
+    /// Ids of the subtasks of `task_id` at any depth
+    pub fn get_descendant_ids(&self, task_id: i64) -> Result<Vec<i64>> {
+        let mut stmt = self.conn.prepare(
+            "WITH RECURSIVE below(id) AS (
+                 SELECT id FROM tasks WHERE parent_id = ?1
+                 UNION ALL
+                 SELECT t.id FROM tasks t JOIN below ON t.parent_id = below.id
+             )
+             SELECT id FROM below ORDER BY id"
+        )?;
+        let ids = stmt
+            .query_map(params![task_id], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ids)
+    }
+
+    pub fn count_subtasks(&self, task_id: i64) -> Result<i64> {
+        let count = self.conn.query_row(
+            "SELECT COUNT(*) FROM tasks WHERE parent_id = ?1",
+            params![task_id],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    // Dependencies
+    pub fn set_task_dependencies(&mut self, task_id: i64, depends_on: &[i64]) -> Result<()> {
+        let tx = self.conn.savepoint()?;
+        Self::write_dependencies(&tx, task_id, depends_on)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Replace a task's prerequisites. Ids of tasks that no longer exist
+    /// are skipped, so undo can restore a snapshot after one was deleted.
+    fn write_dependencies(conn: &Connection, task_id: i64, depends_on: &[i64]) -> Result<()> {
+        conn.execute("DELETE FROM task_dependencies WHERE task_id = ?1", params![task_id])?;
+        for id in depends_on {
+            conn.execute(
+                "INSERT OR IGNORE INTO task_dependencies (task_id, depends_on_id)
+                 SELECT ?1, id FROM tasks WHERE id = ?2",
+                params![task_id, id],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Every task's prerequisites, keyed by task id
+    pub fn get_dependency_graph(&self) -> Result<HashMap<i64, Vec<i64>>> {
+        let mut stmt = self.conn.prepare("SELECT task_id, depends_on_id FROM task_dependencies")?;
+        let mut graph: HashMap<i64, Vec<i64>> = HashMap::new();
+        let edges = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        for edge in edges {
+            let (task_id, depends_on_id) = edge?;
+            graph.entry(task_id).or_default().push(depends_on_id);
+        }
+        Ok(graph)
+    }
+
+    /// Tasks that list `task_id` as a prerequisite
+    pub fn get_dependents(&self, task_id: i64) -> Result<Vec<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT task_id FROM task_dependencies WHERE depends_on_id = ?1 ORDER BY task_id")?;
+        let dependents = stmt
+            .query_map(params![task_id], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(dependents)
+    }
+
+    // Tags
+    pub fn set_task_tags(&mut self, task_id: i64, tags: &[String]) -> Result<()> {
+        let tx = self.conn.savepoint()?;
+        Self::write_tags(&tx, task_id, tags)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn write_tags(conn: &Connection, task_id: i64, tags: &[String]) -> Result<()> {
+        conn.execute("DELETE FROM task_tags WHERE task_id = ?1", params![task_id])?;
+        for tag in tags {
+            conn.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", params![tag])?;
+            conn.execute(
+                "INSERT OR IGNORE INTO task_tags (task_id, tag_id)
+                 SELECT ?1, id FROM tags WHERE name = ?2",
+                params![task_id, tag],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Tags attached to at least one task
+    pub fn get_tags_in_use(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT g.name FROM tags g JOIN task_tags tt ON tt.tag_id = g.id
+             ORDER BY g.name"
+        )?;
+        let tags = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tags)
+    }
+
+    pub fn get_tag_stats(&self) -> Result<Vec<TagStats>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT g.name, COUNT(*), SUM(t.points_earned), CAST(COALESCE(SUM(f.active_time), 0) AS INTEGER)
+             FROM tags g
+             JOIN task_tags tt ON tt.tag_id = g.id
+             JOIN tasks t ON t.id = tt.task_id
+             LEFT JOIN (
+                 SELECT task_id,
+                        SUM((JULIANDAY(ended_at) - JULIANDAY(started_at)) * 86400) as active_time
+                 FROM time_entries
+                 WHERE ended_at IS NOT NULL
+                 GROUP BY task_id
+             ) f ON f.task_id = t.id
+             WHERE t.status = 'Completed'
+             GROUP BY g.name
+             ORDER BY SUM(t.points_earned) DESC"
+        )?;
+
+        let stats = stmt
+            .query_map([], |row| {
+                Ok(TagStats {
+                    tag: row.get(0)?,
+                    tasks_completed: row.get(1)?,
+                    points_earned: row.get(2)?,
+                    focus_time: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(stats)
+    }
+
+    // Recurring series
+    pub fn start_series(&mut self, rule: &Recurrence, created_at: DateTime<Utc>) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO series (rule, created_at) VALUES (?1, ?2)",
+            params![rule.to_string(), created_at.to_rfc3339()],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Change the rule of a series, or stop it (None) so completing its
+    /// open instance no longer creates another
+    pub fn set_series_rule(&mut self, series_id: i64, rule: Option<&Recurrence>) -> Result<()> {
+        Self::write_series_rule(&self.conn, series_id, rule)
+    }
+
+    fn write_series_rule(conn: &Connection, series_id: i64, rule: Option<&Recurrence>) -> Result<()> {
+        conn.execute(
+            "UPDATE series SET rule = COALESCE(?2, rule), active = ?3 WHERE id = ?1",
+            params![series_id, rule.map(|r| r.to_string()), rule.is_some()],
+        )?;
+        Ok(())
+    }
+
+    /// Completion rate of every series with at least one instance. A due
+    /// date counts as an occurrence once it has passed, or as soon as its
+    /// instance is done.
+    pub fn get_series_stats(&self, today: NaiveDate) -> Result<Vec<SeriesStats>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.id, s.rule, s.active,
+                    (SELECT title FROM tasks WHERE series_id = s.id ORDER BY id DESC LIMIT 1),
+                    MIN(t.due_date), MAX(t.due_date),
+                    SUM(t.status = 'Completed'),
+                    SUM(t.status = 'Completed' AND t.due_date >= ?1),
+                    SUM(t.points_earned)
+             FROM series s
+             JOIN tasks t ON t.series_id = s.id
+             GROUP BY s.id
+             ORDER BY s.active DESC, s.id"
+        )?;
+
+        let rows = stmt
+            .query_map(params![today], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, bool>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<NaiveDate>>(4)?,
+                    row.get::<_, Option<NaiveDate>>(5)?,
+                    row.get::<_, i32>(6)?,
+                    row.get::<_, i32>(7)?,
+                    row.get::<_, i64>(8)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let yesterday = today - chrono::Duration::days(1);
+        let stats = rows
+            .into_iter()
+            .filter_map(|(series_id, rule, active, title, first, last, completed, completed_early, points)| {
+                let rule: Recurrence = rule.parse().ok()?;
+                // A stopped series has no occurrences past its last instance
+                let end = if active { Some(yesterday) } else { last.map(|l| l.min(yesterday)) };
+                let passed = match (first, end) {
+                    (Some(first), Some(end)) => rule.count_between(first, end),
+                    _ => 0,
+                };
+                let occurrences = (passed + completed_early).max(completed);
+                Some(SeriesStats {
+                    series_id,
+                    title,
+                    rule,
+                    active,
+                    instances_completed: completed,
+                    occurrences,
+                    completion_rate: (occurrences > 0).then(|| completed as f64 / occurrences as f64),
+                    points_earned: points,
+                })
+            })
+            .collect();
+        Ok(stats)
+    }
+
+    // Projects
+    pub fn get_projects(&self) -> Result<Vec<Project>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, created_at FROM projects ORDER BY name COLLATE NOCASE"
+        )?;
+        let projects = stmt
+            .query_map([], Self::row_to_project)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(projects)
+    }
+
+    pub fn get_project(&self, id: i64) -> Result<Option<Project>> {
+        let project = self
+            .conn
+            .query_row(
+                "SELECT id, name, created_at FROM projects WHERE id = ?1",
+                params![id],
+                Self::row_to_project,
+            )
+            .optional()?;
+        Ok(project)
+    }
+
+    pub fn find_project(&self, name: &str) -> Result<Option<Project>> {
+        let project = self
+            .conn
+            .query_row(
+                "SELECT id, name, created_at FROM projects WHERE name = ?1",
+                params![name],
+                Self::row_to_project,
+            )
+            .optional()?;
+        Ok(project)
+    }
+
+    /// The project called `name` (case-insensitive), created if missing
+    pub fn get_or_create_project(&mut self, name: &str, created_at: DateTime<Utc>) -> Result<Project> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO projects (name, created_at) VALUES (?1, ?2)",
+            params![name, created_at.to_rfc3339()],
+        )?;
+        self.find_project(name)?
+            .with_context(|| format!("Project '{}' could not be created", name))
+    }
+
+    /// Completed tasks, points and focus time of one project, or of all
+    /// tasks when `project` is None
+    pub fn get_project_stats(&self, project: Option<i64>) -> Result<ProjectStats> {
+        let stats = self.conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(t.points_earned), 0),
+                    CAST(COALESCE(SUM(f.active_time), 0) AS INTEGER)
+             FROM tasks t
+             LEFT JOIN (
+                 SELECT task_id,
+                        SUM((JULIANDAY(ended_at) - JULIANDAY(started_at)) * 86400) as active_time
+                 FROM time_entries
+                 WHERE ended_at IS NOT NULL
+                 GROUP BY task_id
+             ) f ON f.task_id = t.id
+             WHERE t.status = 'Completed' AND (?1 IS NULL OR t.project_id = ?1)",
+            params![project],
+            |row| {
+                Ok(ProjectStats {
+                    tasks_completed: row.get(0)?,
+                    points_earned: row.get(1)?,
+                    focus_time: row.get(2)?,
+                })
+            },
+        )?;
+        Ok(stats)
+    }
+
     // User stats operations
     pub fn get_user_stats(&self) -> Result<UserStats> {
         let mut stmt = self.conn.prepare(
-            "SELECT total_points, current_streak, longest_streak, last_completion_date,
+            "SELECT (SELECT COALESCE(SUM(points), 0) FROM points_ledger),
+                    current_streak, longest_streak, last_completion_date,
                     tasks_completed, total_focus_time, streak_bonus_pool
              FROM user_stats WHERE id = 1"
         )?;
@@ -194,7 +819,7 @@ impl Database {
                 total_points: row.get(0)?,
                 current_streak: row.get(1)?,
                 longest_streak: row.get(2)?,
-                last_completion_date: row.get::<_, Option<String>>(3)?.map(|s| Self::parse_datetime(s)),
+                last_completion_date: Self::get_optional_datetime(row, 3)?,
                 tasks_completed: row.get(4)?,
                 total_focus_time: row.get(5)?,
                 streak_bonus_pool: row.get(6)?,
@@ -223,53 +848,260 @@ impl Database {
         Ok(())
     }
 
+    /// Rewrite the cached counters in `user_stats` from the ledger and task
+    /// history. Streak state has no other source and is left untouched.
+    pub fn rebuild_user_stats(&mut self) -> Result<UserStats> {
+        let mut stats = self.get_user_stats()?;
+
+        stats.tasks_completed = self.conn.query_row(
+            "SELECT COUNT(*) FROM tasks WHERE status = 'Completed'",
+            [],
+            |row| row.get(0),
+        )?;
+        stats.total_focus_time = self.conn.query_row(
+            "SELECT COALESCE(SUM((JULIANDAY(e.ended_at) - JULIANDAY(e.started_at)) * 86400), 0)
+             FROM time_entries e JOIN tasks t ON t.id = e.task_id
+             WHERE t.status = 'Completed' AND e.ended_at IS NOT NULL",
+            [],
+            |row| row.get::<_, f64>(0),
+        )? as i64;
+
+        self.update_user_stats(&stats)?;
+        Ok(stats)
+    }
+
+    // Points ledger operations
+    pub fn record_points(
+        &mut self,
+        task_id: Option<i64>,
+        grants: &[PointGrant],
+        at: DateTime<Utc>,
+    ) -> Result<()> {
+        let tx = self.conn.savepoint()?;
+        for grant in grants {
+            tx.execute(
+                "INSERT INTO points_ledger (task_id, reason, points, created_at) VALUES (?1, ?2, ?3, ?4)",
+                params![task_id, Self::reason_to_string(&grant.reason), grant.points, at.to_rfc3339()],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_task_ledger(&self, task_id: i64) -> Result<Vec<LedgerEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, task_id, reason, points, created_at
+             FROM points_ledger WHERE task_id = ?1
+             ORDER BY id"
+        )?;
+
+        let entries = stmt
+            .query_map(params![task_id], |row| {
+                Ok(LedgerEntry {
+                    id: row.get(0)?,
+                    task_id: row.get(1)?,
+                    reason: Self::parse_reason(row.get::<_, String>(2)?),
+                    points: row.get(3)?,
+                    created_at: Self::get_datetime(row, 4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(entries)
+    }
+
+    /// Points in ledger rows that belong to none of `task_ids`: imported
+    /// totals, grants without a task and the rows of deleted tasks
+    pub fn get_ledger_points_excluding(&self, task_ids: &HashSet<i64>) -> Result<i32> {
+        let mut stmt = self.conn.prepare("SELECT task_id, SUM(points) FROM points_ledger GROUP BY task_id")?;
+        let mut total = 0;
+        let sums = stmt.query_map([], |row| Ok((row.get::<_, Option<i64>>(0)?, row.get::<_, i32>(1)?)))?;
+        for sum in sums {
+            let (task_id, points) = sum?;
+            if !task_id.is_some_and(|id| task_ids.contains(&id)) {
+                total += points;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Lifetime points per reason, largest contribution first
+    pub fn get_points_by_reason(&self) -> Result<Vec<(PointReason, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT reason, SUM(points) as total
+             FROM points_ledger
+             GROUP BY reason
+             ORDER BY total DESC"
+        )?;
+
+        let totals = stmt
+            .query_map([], |row| {
+                Ok((Self::parse_reason(row.get::<_, String>(0)?), row.get(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(totals)
+    }
+
+    /// Write a replayed history back: task points, regenerated ledger rows
+    /// for the rescored tasks and the stats row, all in one transaction.
+    /// Ledger rows of other tasks, or of none, are kept.
+    pub fn apply_recompute(&mut self, recompute: &Recompute) -> Result<()> {
+        let tx = self.conn.savepoint()?;
+
+        for rescore in &recompute.tasks {
+            let task = &rescore.task;
+            tx.execute("DELETE FROM points_ledger WHERE task_id = ?1", params![task.id])?;
+            tx.execute(
+                "UPDATE tasks SET points_earned = ?1, points_breakdown = ?2 WHERE id = ?3",
+                params![
+                    task.points_earned,
+                    Self::breakdown_to_json(&task.points_breakdown)?,
+                    task.id,
+                ],
+            )?;
+
+            let created_at = task.completed_at.map(|dt| dt.to_rfc3339());
+            for grant in rescore.breakdown.grants().iter().chain(rescore.milestones.iter()) {
+                tx.execute(
+                    "INSERT INTO points_ledger (task_id, reason, points, created_at) VALUES (?1, ?2, ?3, ?4)",
+                    params![task.id, Self::reason_to_string(&grant.reason), grant.points, created_at],
+                )?;
+            }
+        }
+
+        let stats = &recompute.after;
+        tx.execute(
+            "UPDATE user_stats SET total_points = ?1, current_streak = ?2, longest_streak = ?3,
+                    last_completion_date = ?4, tasks_completed = ?5, total_focus_time = ?6,
+                    streak_bonus_pool = ?7
+             WHERE id = 1",
+            params![
+                stats.total_points,
+                stats.current_streak,
+                stats.longest_streak,
+                stats.last_completion_date.map(|dt| dt.to_rfc3339()),
+                stats.tasks_completed,
+                stats.total_focus_time,
+                stats.streak_bonus_pool,
+            ],
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    // Settings
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let value = self
+            .conn
+            .query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()?;
+        Ok(value)
+    }
+
+    pub fn set_setting(&mut self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
     // Analytics
-    pub fn get_daily_stats(&self, days: i32) -> Result<Vec<DailyStats>> {
-        let cutoff = Utc::now() - chrono::Duration::days(days as i64);
+    /// Per-day totals for the last `days` days (today included, newest
+    /// first), with days counted by `boundary`. `project` limits them to
+    /// that project's tasks.
+    pub fn get_daily_stats(
+        &self,
+        days: i32,
+        now: DateTime<Utc>,
+        boundary: &DayBoundary,
+        project: Option<i64>,
+    ) -> Result<Vec<DailyStats>> {
+        let first_day = boundary.day_of(now) - chrono::Duration::days(days.max(1) as i64 - 1);
+        let cutoff = boundary.start_of(first_day);
         let cutoff_str = cutoff.to_rfc3339();
+        let mut by_day: BTreeMap<NaiveDate, DailyStats> = BTreeMap::new();
 
+        // Completions and points are charged to the day the task was finished
         let mut stmt = self.conn.prepare(
-            "SELECT DATE(completed_at) as date,
-                    COUNT(*) as tasks_completed,
-                    SUM(points_earned) as points_earned,
-                    SUM((JULIANDAY(completed_at) - JULIANDAY(started_at)) * 86400 - total_pause_duration) as focus_time
+            "SELECT completed_at, points_earned
              FROM tasks
              WHERE status = 'Completed' AND completed_at >= ?1
-             GROUP BY DATE(completed_at)
-             ORDER BY date DESC"
+               AND (?2 IS NULL OR project_id = ?2)"
         )?;
 
-        let stats = stmt
-            .query_map(params![cutoff_str], |row| {
-                let date_str: String = row.get(0)?;
-                let date = NaiveDateTime::parse_from_str(&format!("{} 00:00:00", date_str), "%Y-%m-%d %H:%M:%S")
-                    .ok()
-                    .and_then(|naive| Utc.from_local_datetime(&naive).single())
-                    .unwrap_or_else(Utc::now);
-
-                Ok(DailyStats {
-                    date,
-                    tasks_completed: row.get(1)?,
-                    points_earned: row.get(2)?,
-                    focus_time: row.get::<_, Option<i64>>(3)?.unwrap_or(0),
-                })
+        let completions = stmt
+            .query_map(params![cutoff_str, project], |row| {
+                Ok((Self::get_datetime(row, 0)?, row.get::<_, i32>(1)?))
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(stats)
+        for (completed_at, points_earned) in completions {
+            let day = boundary.day_of(completed_at);
+            let entry = by_day.entry(day).or_insert_with(|| DailyStats::empty(day));
+            entry.tasks_completed += 1;
+            entry.points_earned += points_earned;
+        }
+
+        // Focus time is charged to the days the work actually happened,
+        // splitting intervals that cross a day boundary
+        let mut stmt = self.conn.prepare(
+            "SELECT started_at, ended_at FROM time_entries
+             WHERE (ended_at IS NULL OR ended_at >= ?1)
+               AND (?2 IS NULL OR task_id IN (SELECT id FROM tasks WHERE project_id = ?2))"
+        )?;
+
+        let intervals = stmt
+            .query_map(params![cutoff_str, project], |row| {
+                Ok((
+                    Self::get_datetime(row, 0)?,
+                    Self::get_optional_datetime(row, 1)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (started_at, ended_at) in intervals {
+            let mut cursor = started_at.max(cutoff);
+            let end = ended_at.unwrap_or(now);
+
+            while cursor < end {
+                let day = boundary.day_of(cursor);
+                let next_day = day
+                    .succ_opt()
+                    .map(|next| boundary.start_of(next))
+                    .filter(|start| *start > cursor)
+                    .unwrap_or(end);
+                let slice_end = end.min(next_day);
+
+                by_day.entry(day).or_insert_with(|| DailyStats::empty(day)).focus_time +=
+                    (slice_end - cursor).num_seconds();
+                cursor = slice_end;
+            }
+        }
+
+        Ok(by_day.into_values().rev().collect())
     }
 
-    pub fn get_avg_time_by_difficulty(&self) -> Result<Vec<(i32, f64)>> {
+    pub fn get_avg_time_by_difficulty(&self, project: Option<i64>) -> Result<Vec<(i32, f64)>> {
         let mut stmt = self.conn.prepare(
-            "SELECT difficulty,
-                    AVG((JULIANDAY(completed_at) - JULIANDAY(started_at)) * 86400 - total_pause_duration) as avg_time
-             FROM tasks
-             WHERE status = 'Completed' AND started_at IS NOT NULL AND completed_at IS NOT NULL
-             GROUP BY difficulty"
+            "SELECT t.difficulty, AVG(e.active_time) as avg_time
+             FROM tasks t
+             JOIN (
+                 SELECT task_id,
+                        SUM((JULIANDAY(ended_at) - JULIANDAY(started_at)) * 86400) as active_time
+                 FROM time_entries
+                 WHERE ended_at IS NOT NULL
+                 GROUP BY task_id
+             ) e ON e.task_id = t.id
+             WHERE t.status = 'Completed' AND (?1 IS NULL OR t.project_id = ?1)
+             GROUP BY t.difficulty"
         )?;
 
         let avgs = stmt
-            .query_map([], |row| {
+            .query_map(params![project], |row| {
                 Ok((row.get(0)?, row.get(1)?))
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -277,37 +1109,146 @@ impl Database {
         Ok(avgs)
     }
 
-    pub fn get_completed_tasks(&self, limit: usize) -> Result<Vec<Task>> {
+    /// (difficulty, active seconds) of every completed task with tracked
+    /// time, for difficulty calibration
+    pub fn get_completion_times(&self, project: Option<i64>) -> Result<Vec<(i32, i64)>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, title, difficulty, status, created_at, started_at, completed_at,
-                    paused_at, total_pause_duration, points_earned
-             FROM tasks
-             WHERE status = 'Completed'
+            "SELECT t.difficulty, CAST(e.active_time AS INTEGER)
+             FROM tasks t
+             JOIN (
+                 SELECT task_id,
+                        SUM((JULIANDAY(ended_at) - JULIANDAY(started_at)) * 86400) as active_time
+                 FROM time_entries
+                 WHERE ended_at IS NOT NULL
+                 GROUP BY task_id
+             ) e ON e.task_id = t.id
+             WHERE t.status = 'Completed' AND e.active_time > 0
+               AND (?1 IS NULL OR t.project_id = ?1)
+             ORDER BY t.difficulty"
+        )?;
+
+        let times = stmt
+            .query_map(params![project], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(times)
+    }
+
+    pub fn get_completed_tasks(&self, limit: usize, project: Option<i64>) -> Result<Vec<Task>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM tasks
+             WHERE status = 'Completed' AND (?2 IS NULL OR project_id = ?2)
              ORDER BY completed_at DESC
-             LIMIT ?1"
+             LIMIT ?1",
+            TASK_COLUMNS
+        ))?;
+
+        let mut tasks = stmt
+            .query_map(params![limit, project], Self::row_to_task)?
+            .collect::<Result<Vec<_>, _>>()?;
+        self.attach_related(&mut tasks)?;
+
+        Ok(tasks)
+    }
+
+    /// Completed tasks that had an estimate and recorded time, oldest
+    /// completion first
+    pub fn get_estimate_samples(&self, project: Option<i64>) -> Result<Vec<EstimateSample>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.id, t.completed_at, t.difficulty, t.estimate_minutes,
+                    CAST(e.active_time AS INTEGER)
+             FROM tasks t
+             JOIN (
+                 SELECT task_id,
+                        SUM((JULIANDAY(ended_at) - JULIANDAY(started_at)) * 86400) as active_time
+                 FROM time_entries
+                 WHERE ended_at IS NOT NULL
+                 GROUP BY task_id
+             ) e ON e.task_id = t.id
+             WHERE t.status = 'Completed' AND t.completed_at IS NOT NULL
+               AND t.estimate_minutes > 0 AND e.active_time > 0
+               AND (?1 IS NULL OR t.project_id = ?1)
+             ORDER BY t.completed_at ASC, t.id ASC"
         )?;
 
-        let tasks = stmt
-            .query_map(params![limit], |row| {
-                Ok(Task {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
+        let samples = stmt
+            .query_map(params![project], |row| {
+                Ok(EstimateSample {
+                    task_id: row.get(0)?,
+                    completed_at: Self::get_datetime(row, 1)?,
                     difficulty: row.get(2)?,
-                    status: Self::parse_status(row.get::<_, String>(3)?),
-                    created_at: Self::parse_datetime(row.get(4)?),
-                    started_at: row.get::<_, Option<String>>(5)?.map(|s| Self::parse_datetime(s)),
-                    completed_at: row.get::<_, Option<String>>(6)?.map(|s| Self::parse_datetime(s)),
-                    paused_at: row.get::<_, Option<String>>(7)?.map(|s| Self::parse_datetime(s)),
-                    total_pause_duration: row.get(8)?,
-                    points_earned: row.get(9)?,
+                    estimate_minutes: row.get(3)?,
+                    actual_seconds: row.get(4)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
+        Ok(samples)
+    }
+
+    /// Every completed task, oldest completion first
+    pub fn get_completion_history(&self) -> Result<Vec<Task>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM tasks
+             WHERE status = 'Completed' AND completed_at IS NOT NULL
+             ORDER BY completed_at ASC, id ASC",
+            TASK_COLUMNS
+        ))?;
+
+        let mut tasks = stmt
+            .query_map([], Self::row_to_task)?
+            .collect::<Result<Vec<_>, _>>()?;
+        self.attach_related(&mut tasks)?;
 
         Ok(tasks)
     }
 
     // Helper functions
+    fn row_to_task(row: &Row) -> rusqlite::Result<Task> {
+        Ok(Task {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            difficulty: row.get(2)?,
+            status: Self::parse_status(row.get::<_, String>(3)?),
+            created_at: Self::get_datetime(row, 4)?,
+            started_at: Self::get_optional_datetime(row, 5)?,
+            completed_at: Self::get_optional_datetime(row, 6)?,
+            points_earned: row.get(7)?,
+            points_breakdown: row
+                .get::<_, Option<String>>(8)?
+                .and_then(|json| serde_json::from_str(&json).ok()),
+            time_entries: Vec::new(),
+            tags: Vec::new(),
+            project_id: row.get(9)?,
+            project: row.get(10)?,
+            parent_id: row.get(11)?,
+            depends_on: Vec::new(),
+            blocked_by: Vec::new(),
+            due_date: row.get(12)?,
+            series_id: row.get(13)?,
+            recurrence: row
+                .get::<_, Option<String>>(14)?
+                .and_then(|rule| rule.parse().ok()),
+            notes: row.get(15)?,
+            estimate_minutes: row.get(16)?,
+        })
+    }
+
+    fn row_to_project(row: &Row) -> rusqlite::Result<Project> {
+        Ok(Project {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            created_at: Self::get_datetime(row, 2)?,
+        })
+    }
+
+    fn row_to_time_entry(row: &Row) -> rusqlite::Result<TimeEntry> {
+        Ok(TimeEntry {
+            id: row.get(0)?,
+            task_id: row.get(1)?,
+            started_at: Self::get_datetime(row, 2)?,
+            ended_at: Self::get_optional_datetime(row, 3)?,
+        })
+    }
+
     fn parse_status(s: String) -> TaskStatus {
         match s.as_str() {
             "Pending" => TaskStatus::Pending,
@@ -327,9 +1268,114 @@ impl Database {
         }
     }
 
-    fn parse_datetime(s: String) -> DateTime<Utc> {
-        DateTime::parse_from_rfc3339(&s)
+    fn breakdown_to_json(breakdown: &Option<PointsBreakdown>) -> Result<Option<String>> {
+        Ok(breakdown.as_ref().map(serde_json::to_string).transpose()?)
+    }
+
+    fn parse_reason(s: String) -> PointReason {
+        match s.as_str() {
+            "TaskBase" => PointReason::TaskBase,
+            "TimeBonus" => PointReason::TimeBonus,
+            "StreakMultiplier" => PointReason::StreakMultiplier,
+            "FocusBonus" => PointReason::FocusBonus,
+            "AccuracyBonus" => PointReason::AccuracyBonus,
+            "OnTimeBonus" => PointReason::OnTimeBonus,
+            "MinimumFloor" => PointReason::MinimumFloor,
+            "Milestone" => PointReason::Milestone,
+            _ => PointReason::Imported,
+        }
+    }
+
+    fn reason_to_string(reason: &PointReason) -> String {
+        match reason {
+            PointReason::TaskBase => "TaskBase".to_string(),
+            PointReason::TimeBonus => "TimeBonus".to_string(),
+            PointReason::StreakMultiplier => "StreakMultiplier".to_string(),
+            PointReason::FocusBonus => "FocusBonus".to_string(),
+            PointReason::AccuracyBonus => "AccuracyBonus".to_string(),
+            PointReason::OnTimeBonus => "OnTimeBonus".to_string(),
+            PointReason::MinimumFloor => "MinimumFloor".to_string(),
+            PointReason::Milestone => "Milestone".to_string(),
+            PointReason::Imported => "Imported".to_string(),
+        }
+    }
+
+    fn parse_event_kind(s: String) -> TaskEventKind {
+        match s.as_str() {
+            "Created" => TaskEventKind::Created,
+            "Started" => TaskEventKind::Started,
+            "Paused" => TaskEventKind::Paused,
+            "Resumed" => TaskEventKind::Resumed,
+            "Completed" => TaskEventKind::Completed,
+            "Deleted" => TaskEventKind::Deleted,
+            "Undone" => TaskEventKind::Undone,
+            "Redone" => TaskEventKind::Redone,
+            _ => TaskEventKind::Edited,
+        }
+    }
+
+    /// Timestamps are stored as RFC 3339. Anything else is reported as an
+    /// error rather than replaced with the current time.
+    fn get_datetime(row: &Row, idx: usize) -> rusqlite::Result<DateTime<Utc>> {
+        let value: String = row.get(idx)?;
+        DateTime::parse_from_rfc3339(&value)
             .map(|dt| dt.with_timezone(&Utc))
-            .unwrap_or_else(|_| Utc::now())
+            .map_err(|e| {
+                let message = format!("invalid timestamp '{}': {}", value, e);
+                rusqlite::Error::FromSqlConversionFailure(idx, Type::Text, message.into())
+            })
+    }
+
+    fn get_optional_datetime(row: &Row, idx: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+        match row.get_ref(idx)? {
+            ValueRef::Null => Ok(None),
+            _ => Self::get_datetime(row, idx).map(Some),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, FakeClock};
+    use chrono::{NaiveTime, TimeZone};
+    use chrono_tz::Tz;
+
+    #[test]
+    fn time_and_daily_stats_follow_the_clock() -> Result<()> {
+        let clock = FakeClock::new(Utc.with_ymd_and_hms(2026, 3, 2, 23, 30, 0).unwrap());
+        let boundary = DayBoundary::new(Some(Tz::UTC), NaiveTime::MIN);
+        let mut db = Database::open_in_memory(clock.now())?;
+
+        let task = db.create_task(&NewTask::new("Write tests".to_string(), 3), clock.now())?;
+        db.start_time_entry(task.id, clock.now())?;
+        clock.advance(chrono::Duration::hours(1));
+        let running = db.get_task(task.id)?.unwrap();
+        assert_eq!(running.active_seconds_at(clock.now()), 3600);
+
+        // Paused for two hours, then another quarter of an hour
+        db.stop_time_entry(task.id, clock.now())?;
+        clock.advance(chrono::Duration::hours(2));
+        db.start_time_entry(task.id, clock.now())?;
+        clock.advance(chrono::Duration::minutes(15));
+        db.stop_time_entry(task.id, clock.now())?;
+
+        let mut done = db.get_task(task.id)?.unwrap();
+        assert_eq!(done.active_seconds_at(clock.now() + chrono::Duration::days(1)), 75 * 60);
+        done.status = TaskStatus::Completed;
+        done.completed_at = Some(clock.now());
+        done.points_earned = 30;
+        db.update_task(&done)?;
+
+        // Newest day first; the first interval is split at midnight
+        let days = db.get_daily_stats(2, clock.now(), &boundary, None)?;
+        let focus: Vec<i64> = days.iter().map(|day| day.focus_time).collect();
+        assert_eq!(focus, vec![45 * 60, 30 * 60]);
+        assert_eq!((days[0].tasks_completed, days[0].points_earned), (1, 30));
+        assert_eq!((days[1].tasks_completed, days[1].points_earned), (0, 0));
+
+        clock.set(Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap());
+        assert!(db.get_daily_stats(1, clock.now(), &boundary, None)?.is_empty());
+        Ok(())
     }
 }
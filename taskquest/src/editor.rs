@@ -0,0 +1,213 @@
+/// Multiline text buffer behind the notes popup. Positions are counted in
+/// characters, not bytes, so non-ASCII text moves one glyph at a time.
+#[derive(Debug, Clone)]
+pub struct TextEditor {
+    lines: Vec<String>,
+    row: usize,
+    col: usize,
+    scroll_row: usize,
+    scroll_col: usize,
+}
+
+impl Default for TextEditor {
+    fn default() -> Self {
+        Self {
+            lines: vec![String::new()],
+            row: 0,
+            col: 0,
+            scroll_row: 0,
+            scroll_col: 0,
+        }
+    }
+}
+
+impl TextEditor {
+    /// Editor holding `text` with the cursor at its end
+    pub fn with_text(text: &str) -> Self {
+        let mut editor = Self::default();
+        editor.insert_str(text);
+        editor
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// Cursor as (row, column)
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    fn line_len(&self, row: usize) -> usize {
+        self.lines[row].chars().count()
+    }
+
+    fn byte_index(&self, row: usize, col: usize) -> usize {
+        self.lines[row]
+            .char_indices()
+            .nth(col)
+            .map_or(self.lines[row].len(), |(i, _)| i)
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        if c == '\n' {
+            self.newline();
+            return;
+        }
+        let at = self.byte_index(self.row, self.col);
+        self.lines[self.row].insert(at, c);
+        self.col += 1;
+    }
+
+    /// Insert pasted text; `\r\n` and lone `\r` count as line breaks and
+    /// tabs become spaces
+    pub fn insert_str(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n").replace('\t', "    ");
+        for c in text.chars().filter(|c| *c == '\n' || !c.is_control()) {
+            self.insert_char(c);
+        }
+    }
+
+    pub fn newline(&mut self) {
+        let at = self.byte_index(self.row, self.col);
+        let rest = self.lines[self.row].split_off(at);
+        self.row += 1;
+        self.col = 0;
+        self.lines.insert(self.row, rest);
+    }
+
+    /// Delete before the cursor, joining with the previous line at column 0
+    pub fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            let at = self.byte_index(self.row, self.col);
+            self.lines[self.row].remove(at);
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_len(self.row);
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    /// Delete under the cursor, joining with the next line at the end
+    pub fn delete(&mut self) {
+        if self.col < self.line_len(self.row) {
+            let at = self.byte_index(self.row, self.col);
+            self.lines[self.row].remove(at);
+        } else if self.row + 1 < self.lines.len() {
+            let line = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.line_len(self.row);
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if self.col < self.line_len(self.row) {
+            self.col += 1;
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    pub fn move_up(&mut self) {
+        if self.row > 0 {
+            self.row -= 1;
+            self.col = self.col.min(self.line_len(self.row));
+        }
+    }
+
+    pub fn move_down(&mut self) {
+        if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = self.col.min(self.line_len(self.row));
+        }
+    }
+
+    pub fn move_home(&mut self) {
+        self.col = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.col = self.line_len(self.row);
+    }
+
+    /// Start of the previous word, crossing to the previous line at column 0
+    pub fn move_word_left(&mut self) {
+        if self.col == 0 {
+            self.move_left();
+            return;
+        }
+        let chars: Vec<char> = self.lines[self.row].chars().collect();
+        let mut col = self.col;
+        while col > 0 && !chars[col - 1].is_alphanumeric() {
+            col -= 1;
+        }
+        while col > 0 && chars[col - 1].is_alphanumeric() {
+            col -= 1;
+        }
+        self.col = col;
+    }
+
+    /// End of the next word, crossing to the next line at the end
+    pub fn move_word_right(&mut self) {
+        let chars: Vec<char> = self.lines[self.row].chars().collect();
+        if self.col == chars.len() {
+            self.move_right();
+            return;
+        }
+        let mut col = self.col;
+        while col < chars.len() && !chars[col].is_alphanumeric() {
+            col += 1;
+        }
+        while col < chars.len() && chars[col].is_alphanumeric() {
+            col += 1;
+        }
+        self.col = col;
+    }
+
+    /// Delete from the start of the previous word to the cursor
+    pub fn delete_word_left(&mut self) {
+        let (row, end) = (self.row, self.col);
+        self.move_word_left();
+        if self.row != row {
+            // At column 0 this just joins the lines, like backspace
+            self.row = row;
+            self.col = end;
+            self.backspace();
+            return;
+        }
+        let (from, to) = (self.byte_index(row, self.col), self.byte_index(row, end));
+        self.lines[row].replace_range(from..to, "");
+    }
+
+    /// Scroll so the cursor stays inside a `width` × `height` view and
+    /// return the (row, column) offsets to draw from
+    pub fn scroll_to_cursor(&mut self, width: usize, height: usize) -> (usize, usize) {
+        let (width, height) = (width.max(1), height.max(1));
+        if self.row < self.scroll_row {
+            self.scroll_row = self.row;
+        } else if self.row >= self.scroll_row + height {
+            self.scroll_row = self.row + 1 - height;
+        }
+        if self.col < self.scroll_col {
+            self.scroll_col = self.col;
+        } else if self.col >= self.scroll_col + width {
+            self.scroll_col = self.col + 1 - width;
+        }
+        (self.scroll_row, self.scroll_col)
+    }
+}
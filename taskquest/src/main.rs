@@ -1,13 +1,25 @@
 mod app;
+mod cli;
+mod clock;
+mod config;
 mod db;
+mod editor;
+mod migrations;
 mod models;
+mod recompute;
 mod scoring;
+mod status;
 mod ui;
+mod undo;
 
 use app::{App, InputMode, View};
 use anyhow::Result;
+use clap::Parser;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, MouseEventKind},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event, KeyCode,
+        KeyEvent, KeyModifiers, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -19,15 +31,32 @@ use std::io;
 use std::time::{Duration, Instant};
 
 fn main() -> Result<()> {
+    let cli = cli::Cli::parse();
+    let clock = clock::from_env()?;
+
+    let command = match cli.command {
+        // Status bars poll this every few seconds; it reads the database
+        // without migrating or writing, so it must not go through App
+        Some(cli::Invocation::Status(args)) => return status::run(&args, clock.as_ref(), cli.json),
+        Some(cli::Invocation::App(command)) => Some(command),
+        None => None,
+    };
+
+    // Open the database (and run migrations) before touching the terminal so
+    // errors are printed to a usable screen
+    let mut app = App::new(clock)?;
+
+    if let Some(command) = command {
+        return cli::run(&mut app, command, cli.json);
+    }
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Create app
-    let mut app = App::new()?;
     let tick_rate = Duration::from_millis(250);
     let res = run_app(&mut terminal, &mut app, tick_rate);
 
@@ -36,7 +65,8 @@ fn main() -> Result<()> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
@@ -65,6 +95,7 @@ fn run_app<B: ratatui::backend::Backend>(
             match event::read()? {
                 Event::Key(key) => handle_key_event(app, key)?,
                 Event::Mouse(mouse) => handle_mouse_event(app, mouse)?,
+                Event::Paste(text) => app.paste(&text),
                 _ => {}
             }
         }
@@ -87,12 +118,24 @@ fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<()> {
     match app.input_mode {
         InputMode::Normal => handle_normal_mode(app, key)?,
         InputMode::AddingTask | InputMode::EditingTask => handle_input_mode(app, key)?,
+        InputMode::ConfirmRecompute => handle_confirm_recompute(app, key)?,
+        InputMode::FilteringTags => handle_tag_filter(app, key),
+        InputMode::PickingProject => handle_project_picker(app, key)?,
+        InputMode::NamingProject => handle_project_name(app, key)?,
+        InputMode::EditingNotes => handle_notes_editor(app, key)?,
+        InputMode::Searching => handle_search(app, key)?,
+        InputMode::FuzzyFiltering => handle_fuzzy_filter(app, key),
     }
     Ok(())
 }
 
 fn handle_normal_mode(app: &mut App, key: KeyEvent) -> Result<()> {
     match key.code {
+        // Esc first drops a fuzzy filter, then quits
+        KeyCode::Esc if !app.fuzzy_filter.is_empty() => {
+            app.clear_fuzzy_filter();
+        }
+
         // Quit
         KeyCode::Char('q') | KeyCode::Esc => {
             app.should_quit = true;
@@ -117,25 +160,76 @@ fn handle_normal_mode(app: &mut App, key: KeyEvent) -> Result<()> {
             app.switch_view(View::Help);
         }
 
+        // Stats maintenance
+        KeyCode::Char('r') if app.current_view == View::Stats => {
+            app.rebuild_stats()?;
+        }
+        KeyCode::Char('R') if app.current_view == View::Stats => {
+            app.preview_recompute()?;
+        }
+
+        // Undo/redo (only in task list view)
+        KeyCode::Char('r')
+            if key.modifiers.contains(KeyModifiers::CONTROL) && app.current_view == View::TaskList =>
+        {
+            app.redo()?;
+        }
+        KeyCode::Char('u') if app.current_view == View::TaskList => {
+            app.undo()?;
+        }
+
         // Task operations (only in task list view)
         KeyCode::Char('a') if app.current_view == View::TaskList => {
             app.start_add_task();
         }
+        KeyCode::Char('A') if app.current_view == View::TaskList => {
+            app.start_add_subtask();
+        }
+        KeyCode::Left | KeyCode::Char('h') if app.current_view == View::TaskList => {
+            app.collapse_selected();
+        }
+        KeyCode::Right | KeyCode::Char('l') if app.current_view == View::TaskList => {
+            app.expand_selected();
+        }
         KeyCode::Char('e') if app.current_view == View::TaskList => {
             app.start_edit_task();
         }
+        KeyCode::Char('n') if app.current_view == View::TaskList => {
+            app.start_edit_notes();
+        }
         KeyCode::Char('d') if app.current_view == View::TaskList => {
             app.delete_selected_task()?;
         }
         KeyCode::Char(' ') if app.current_view == View::TaskList => {
-            app.toggle_task()?;
+            app.toggle_selected_task()?;
         }
         KeyCode::Char('x') if app.current_view == View::TaskList => {
-            app.complete_task()?;
+            app.complete_selected_task()?;
         }
         KeyCode::Char('c') if app.current_view == View::TaskList => {
             app.toggle_completed_view()?;
         }
+        KeyCode::Char('t') if app.current_view == View::TaskList => {
+            app.start_tag_filter()?;
+        }
+        KeyCode::Char('o') if app.current_view == View::TaskList => {
+            app.cycle_sort_key()?;
+        }
+        KeyCode::Char('O') if app.current_view == View::TaskList => {
+            app.reverse_sort()?;
+        }
+        KeyCode::Char('b') if app.current_view == View::TaskList => {
+            app.cycle_secondary_sort()?;
+        }
+        KeyCode::Char('p') if app.current_view != View::Help => {
+            app.start_project_picker()?;
+        }
+        KeyCode::Char('/') => {
+            app.start_search();
+        }
+        KeyCode::Char('f') if app.current_view == View::TaskList => {
+            app.start_fuzzy_filter();
+        }
 
         _ => {}
     }
@@ -164,17 +258,129 @@ fn handle_input_mode(app: &mut App, key: KeyEvent) -> Result<()> {
     Ok(())
 }
 
+fn handle_confirm_recompute(app: &mut App, key: KeyEvent) -> Result<()> {
+    match key.code {
+        KeyCode::Char('y') | KeyCode::Char('Y') => {
+            app.confirm_recompute()?;
+        }
+        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+            app.decline_recompute()?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_tag_filter(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => app.move_tag_cursor(false),
+        KeyCode::Down | KeyCode::Char('j') => app.move_tag_cursor(true),
+        KeyCode::Char(' ') => app.toggle_tag_filter(),
+        KeyCode::Char('c') => app.clear_tag_filter(),
+        KeyCode::Enter | KeyCode::Esc | KeyCode::Char('t') => app.close_tag_filter(),
+        _ => {}
+    }
+}
+
+fn handle_project_picker(app: &mut App, key: KeyEvent) -> Result<()> {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => app.move_project_cursor(false),
+        KeyCode::Down | KeyCode::Char('j') => app.move_project_cursor(true),
+        KeyCode::Enter => app.select_project(),
+        KeyCode::Char('m') if app.current_view == View::TaskList => app.move_selected_task_to_project()?,
+        KeyCode::Esc | KeyCode::Char('p') => app.cancel_input(),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_project_name(app: &mut App, key: KeyEvent) -> Result<()> {
+    match key.code {
+        KeyCode::Esc => app.cancel_input(),
+        KeyCode::Enter => app.submit_project_name()?,
+        KeyCode::Char(c) => app.input_buffer.push(c),
+        KeyCode::Backspace => {
+            app.input_buffer.pop();
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_search(app: &mut App, key: KeyEvent) -> Result<()> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Esc => app.close_search(),
+        KeyCode::Enter => app.open_search_result()?,
+        KeyCode::Up => app.move_search_cursor(false),
+        KeyCode::Down => app.move_search_cursor(true),
+        KeyCode::Char('p') if ctrl => app.move_search_cursor(false),
+        KeyCode::Char('n') if ctrl => app.move_search_cursor(true),
+        KeyCode::Char('u') if ctrl => app.clear_search(),
+        KeyCode::Char(c) if !ctrl => app.search_input_char(c),
+        KeyCode::Backspace => app.search_backspace(),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_fuzzy_filter(app: &mut App, key: KeyEvent) {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Esc => app.clear_fuzzy_filter(),
+        KeyCode::Enter => app.keep_fuzzy_filter(),
+        KeyCode::Up => app.move_selection_up(),
+        KeyCode::Down => app.move_selection_down(),
+        KeyCode::Char('p') if ctrl => app.move_selection_up(),
+        KeyCode::Char('n') if ctrl => app.move_selection_down(),
+        KeyCode::Char(c) if !ctrl => app.fuzzy_filter_char(c),
+        KeyCode::Backspace => app.fuzzy_filter_backspace(),
+        _ => {}
+    }
+}
+
+fn handle_notes_editor(app: &mut App, key: KeyEvent) -> Result<()> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+    let editor = &mut app.notes_editor;
+    match key.code {
+        KeyCode::Esc => app.cancel_input(),
+        KeyCode::Char('s') if ctrl => app.save_notes()?,
+        KeyCode::Char('w') if ctrl => editor.delete_word_left(),
+        KeyCode::Char('a') if ctrl => editor.move_home(),
+        KeyCode::Char('e') if ctrl => editor.move_end(),
+        KeyCode::Char('b') if alt => editor.move_word_left(),
+        KeyCode::Char('f') if alt => editor.move_word_right(),
+        KeyCode::Char(c) if !ctrl => editor.insert_char(c),
+        KeyCode::Enter => editor.newline(),
+        KeyCode::Tab => editor.insert_str("    "),
+        KeyCode::Backspace if ctrl || alt => editor.delete_word_left(),
+        KeyCode::Backspace => editor.backspace(),
+        KeyCode::Delete => editor.delete(),
+        KeyCode::Left if ctrl || alt => editor.move_word_left(),
+        KeyCode::Right if ctrl || alt => editor.move_word_right(),
+        KeyCode::Left => editor.move_left(),
+        KeyCode::Right => editor.move_right(),
+        KeyCode::Up => editor.move_up(),
+        KeyCode::Down => editor.move_down(),
+        KeyCode::Home => editor.move_home(),
+        KeyCode::End => editor.move_end(),
+        _ => {}
+    }
+    Ok(())
+}
+
 fn handle_mouse_event(app: &mut App, mouse: event::MouseEvent) -> Result<()> {
     match mouse.kind {
-        MouseEventKind::ScrollDown => {
-            if app.input_mode == InputMode::Normal && app.current_view == View::TaskList {
-                app.move_selection_down();
-            }
+        MouseEventKind::ScrollDown
+            if app.input_mode == InputMode::Normal && app.current_view == View::TaskList =>
+        {
+            app.move_selection_down();
         }
-        MouseEventKind::ScrollUp => {
-            if app.input_mode == InputMode::Normal && app.current_view == View::TaskList {
-                app.move_selection_up();
-            }
+        MouseEventKind::ScrollUp
+            if app.input_mode == InputMode::Normal && app.current_view == View::TaskList =>
+        {
+            app.move_selection_up();
         }
         MouseEventKind::Down(_button) => {
             // Could implement click-to-select here
@@ -0,0 +1,447 @@
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+
+/// A single schema upgrade step. Steps are applied in order, each inside its
+/// own transaction, and bump `PRAGMA user_version` to `version` on success.
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        // Uses IF NOT EXISTS so databases created before versioning was
+        // introduced (user_version = 0) are adopted without changes.
+        sql: "
+        CREATE TABLE IF NOT EXISTS tasks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            difficulty INTEGER NOT NULL CHECK(difficulty >= 1 AND difficulty <= 10),
+            status TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            started_at TEXT,
+            completed_at TEXT,
+            paused_at TEXT,
+            total_pause_duration INTEGER DEFAULT 0,
+            points_earned INTEGER DEFAULT 0
+        );
+
+        CREATE TABLE IF NOT EXISTS user_stats (
+            id INTEGER PRIMARY KEY CHECK(id = 1),
+            total_points INTEGER DEFAULT 0,
+            current_streak INTEGER DEFAULT 0,
+            longest_streak INTEGER DEFAULT 0,
+            last_completion_date TEXT,
+            tasks_completed INTEGER DEFAULT 0,
+            total_focus_time INTEGER DEFAULT 0,
+            streak_bonus_pool REAL DEFAULT 1.0
+        );
+
+        INSERT OR IGNORE INTO user_stats (id) VALUES (1);
+
+        CREATE INDEX IF NOT EXISTS idx_tasks_status ON tasks(status);
+        CREATE INDEX IF NOT EXISTS idx_tasks_completed_at ON tasks(completed_at);
+        ",
+    },
+    Migration {
+        version: 2,
+        description: "per-session time entries",
+        // Older rows only know their total active time, so each task gets a
+        // single interval of that length ending at its last known event.
+        sql: "
+        CREATE TABLE time_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+            started_at TEXT NOT NULL,
+            ended_at TEXT
+        );
+
+        CREATE INDEX idx_time_entries_task_id ON time_entries(task_id);
+        CREATE INDEX idx_time_entries_started_at ON time_entries(started_at);
+
+        INSERT INTO time_entries (task_id, started_at, ended_at)
+        SELECT id,
+               strftime('%Y-%m-%dT%H:%M:%S', JULIANDAY(completed_at)
+                   - MAX((JULIANDAY(completed_at) - JULIANDAY(started_at)) * 86400 - total_pause_duration, 0) / 86400.0)
+                   || '+00:00',
+               completed_at
+        FROM tasks
+        WHERE status = 'Completed' AND started_at IS NOT NULL AND completed_at IS NOT NULL;
+
+        INSERT INTO time_entries (task_id, started_at, ended_at)
+        SELECT id,
+               strftime('%Y-%m-%dT%H:%M:%S', JULIANDAY(paused_at)
+                   - MAX((JULIANDAY(paused_at) - JULIANDAY(started_at)) * 86400 - total_pause_duration, 0) / 86400.0)
+                   || '+00:00',
+               paused_at
+        FROM tasks
+        WHERE status = 'Paused' AND started_at IS NOT NULL AND paused_at IS NOT NULL;
+
+        INSERT INTO time_entries (task_id, started_at, ended_at)
+        SELECT id,
+               strftime('%Y-%m-%dT%H:%M:%S', JULIANDAY(started_at) + total_pause_duration / 86400.0) || '+00:00',
+               NULL
+        FROM tasks
+        WHERE status = 'InProgress' AND started_at IS NOT NULL;
+
+        ALTER TABLE tasks DROP COLUMN paused_at;
+        ALTER TABLE tasks DROP COLUMN total_pause_duration;
+        ",
+    },
+    Migration {
+        version: 3,
+        description: "append-only task event log",
+        // No foreign key: history must outlive deleted tasks
+        sql: "
+        CREATE TABLE task_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            task_id INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            occurred_at TEXT NOT NULL,
+            detail TEXT
+        );
+
+        CREATE INDEX idx_task_events_task_id ON task_events(task_id, occurred_at);
+
+        CREATE TRIGGER task_events_no_update BEFORE UPDATE ON task_events
+        BEGIN
+            SELECT RAISE(ABORT, 'task_events is append-only');
+        END;
+
+        CREATE TRIGGER task_events_no_delete BEFORE DELETE ON task_events
+        BEGIN
+            SELECT RAISE(ABORT, 'task_events is append-only');
+        END;
+
+        INSERT INTO task_events (task_id, kind, occurred_at)
+        SELECT id, 'Created', created_at FROM tasks;
+
+        INSERT INTO task_events (task_id, kind, occurred_at)
+        SELECT id, 'Started', started_at FROM tasks WHERE started_at IS NOT NULL;
+
+        INSERT INTO task_events (task_id, kind, occurred_at, detail)
+        SELECT id, 'Completed', completed_at, '+' || points_earned || ' points'
+        FROM tasks WHERE status = 'Completed' AND completed_at IS NOT NULL;
+        ",
+    },
+    Migration {
+        version: 4,
+        description: "points ledger",
+        // Points earned before the ledger cannot be split by reason, so they
+        // are imported per task plus one row for whatever user_stats held on
+        // top of that (milestones, deleted tasks).
+        sql: "
+        CREATE TABLE points_ledger (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            task_id INTEGER,
+            reason TEXT NOT NULL,
+            points INTEGER NOT NULL,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE INDEX idx_points_ledger_task_id ON points_ledger(task_id);
+
+        INSERT INTO points_ledger (task_id, reason, points, created_at)
+        SELECT id, 'Imported', points_earned, completed_at
+        FROM tasks
+        WHERE status = 'Completed' AND completed_at IS NOT NULL AND points_earned != 0;
+
+        INSERT INTO points_ledger (task_id, reason, points, created_at)
+        SELECT NULL, 'Imported',
+               total_points - (SELECT COALESCE(SUM(points), 0) FROM points_ledger),
+               COALESCE(last_completion_date, strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now'))
+        FROM user_stats
+        WHERE id = 1 AND total_points != (SELECT COALESCE(SUM(points), 0) FROM points_ledger);
+        ",
+    },
+    Migration {
+        version: 5,
+        description: "per-task scoring breakdown",
+        sql: "
+        ALTER TABLE tasks ADD COLUMN points_breakdown TEXT;
+        ",
+    },
+    Migration {
+        version: 6,
+        description: "key/value settings",
+        sql: "
+        CREATE TABLE settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+        ",
+    },
+    Migration {
+        version: 7,
+        description: "task tags",
+        sql: "
+        CREATE TABLE tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE
+        );
+
+        CREATE TABLE task_tags (
+            task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+            tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+            PRIMARY KEY (task_id, tag_id)
+        );
+
+        CREATE INDEX idx_task_tags_tag_id ON task_tags(tag_id);
+        ",
+    },
+    Migration {
+        version: 8,
+        description: "projects",
+        sql: "
+        CREATE TABLE projects (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            created_at TEXT NOT NULL
+        );
+
+        ALTER TABLE tasks ADD COLUMN project_id INTEGER REFERENCES projects(id) ON DELETE SET NULL;
+
+        CREATE INDEX idx_tasks_project_id ON tasks(project_id);
+        ",
+    },
+    Migration {
+        version: 9,
+        description: "subtasks",
+        sql: "
+        ALTER TABLE tasks ADD COLUMN parent_id INTEGER REFERENCES tasks(id) ON DELETE SET NULL;
+
+        CREATE INDEX idx_tasks_parent_id ON tasks(parent_id);
+        ",
+    },
+    Migration {
+        version: 10,
+        description: "task dependencies",
+        sql: "
+        CREATE TABLE task_dependencies (
+            task_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+            depends_on_id INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+            PRIMARY KEY (task_id, depends_on_id),
+            CHECK (task_id != depends_on_id)
+        );
+
+        CREATE INDEX idx_task_dependencies_depends_on_id ON task_dependencies(depends_on_id);
+        ",
+    },
+    Migration {
+        version: 11,
+        description: "due dates",
+        sql: "
+        ALTER TABLE tasks ADD COLUMN due_date TEXT;
+        ",
+    },
+    Migration {
+        version: 12,
+        description: "recurring series",
+        sql: "
+        CREATE TABLE series (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            rule TEXT NOT NULL,
+            active INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL
+        );
+
+        ALTER TABLE tasks ADD COLUMN series_id INTEGER REFERENCES series(id);
+
+        CREATE INDEX idx_tasks_series_id ON tasks(series_id);
+        ",
+    },
+    Migration {
+        version: 13,
+        description: "task notes",
+        sql: "
+        ALTER TABLE tasks ADD COLUMN notes TEXT NOT NULL DEFAULT '';
+        ",
+    },
+    Migration {
+        version: 14,
+        description: "time estimates",
+        sql: "
+        ALTER TABLE tasks ADD COLUMN estimate_minutes INTEGER;
+        ",
+    },
+    Migration {
+        version: 15,
+        description: "full-text search",
+        // External-content index over tasks; the triggers keep it in step
+        // with every insert, update and delete, including undo restores.
+        sql: "
+        CREATE VIRTUAL TABLE tasks_fts USING fts5(
+            title,
+            notes,
+            content = 'tasks',
+            content_rowid = 'id',
+            tokenize = 'unicode61 remove_diacritics 2',
+            prefix = '2 3'
+        );
+
+        CREATE TRIGGER tasks_fts_insert AFTER INSERT ON tasks BEGIN
+            INSERT INTO tasks_fts(rowid, title, notes) VALUES (new.id, new.title, new.notes);
+        END;
+
+        CREATE TRIGGER tasks_fts_delete AFTER DELETE ON tasks BEGIN
+            INSERT INTO tasks_fts(tasks_fts, rowid, title, notes) VALUES ('delete', old.id, old.title, old.notes);
+        END;
+
+        CREATE TRIGGER tasks_fts_update AFTER UPDATE OF title, notes ON tasks
+        WHEN old.title IS NOT new.title OR old.notes IS NOT new.notes BEGIN
+            INSERT INTO tasks_fts(tasks_fts, rowid, title, notes) VALUES ('delete', old.id, old.title, old.notes);
+            INSERT INTO tasks_fts(rowid, title, notes) VALUES (new.id, new.title, new.notes);
+        END;
+
+        INSERT INTO tasks_fts(tasks_fts) VALUES ('rebuild');
+        ",
+    },
+];
+
+/// Schema version this build of TaskQuest expects
+pub fn latest_version() -> i32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub fn current_version(conn: &Connection) -> Result<i32> {
+    let version = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    Ok(version)
+}
+
+/// Bring the database up to `latest_version()`.
+///
+/// Refuses to touch databases written by a newer TaskQuest, and snapshots
+/// existing data next to `db_path` before applying any step. The snapshot is
+/// named after `now`.
+pub fn migrate(conn: &mut Connection, db_path: &Path, now: DateTime<Utc>) -> Result<()> {
+    let current = current_version(conn)?;
+    let latest = latest_version();
+
+    if current > latest {
+        bail!(
+            "Database {} has schema version {}, but this TaskQuest only supports up to version {}. \
+             Please upgrade TaskQuest.",
+            db_path.display(),
+            current,
+            latest
+        );
+    }
+
+    if current == latest {
+        return Ok(());
+    }
+
+    if has_user_data(conn)? {
+        let backup = backup_path(db_path, current, now);
+        conn.execute("VACUUM INTO ?1", [backup.to_string_lossy()])
+            .with_context(|| format!("Failed to back up database to {}", backup.display()))?;
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration.sql).with_context(|| {
+            format!(
+                "Migration {} ({}) failed",
+                migration.version, migration.description
+            )
+        })?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+fn has_user_data(conn: &Connection) -> Result<bool> {
+    let tables: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(tables > 0)
+}
+
+fn backup_path(db_path: &Path, version: i32, now: DateTime<Utc>) -> PathBuf {
+    let file_name = db_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "tasks.db".to_string());
+    let stamp = now.format("%Y%m%d%H%M%S");
+    db_path.with_file_name(format!("{}.v{}.{}.bak", file_name, version, stamp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 4, 9, 0, 0).unwrap()
+    }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("taskquest-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn unversioned_databases_are_upgraded_with_their_data() {
+        let dir = scratch_dir("migrate");
+        let db_path = dir.join("tasks.db");
+        let mut conn = Connection::open(&db_path).unwrap();
+        // The schema TaskQuest created before it tracked user_version
+        conn.execute_batch(MIGRATIONS[0].sql).unwrap();
+        conn.execute_batch(
+            "INSERT INTO tasks (title, difficulty, status, created_at, started_at, completed_at, points_earned)
+             VALUES ('Write report', 3, 'Completed', '2026-03-01T09:00:00+00:00',
+                     '2026-03-01T09:00:00+00:00', '2026-03-01T10:00:00+00:00', 30);
+             UPDATE user_stats SET total_points = 30, tasks_completed = 1;",
+        )
+        .unwrap();
+        assert_eq!(current_version(&conn).unwrap(), 0);
+
+        migrate(&mut conn, &db_path, now()).unwrap();
+
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        let title: String = conn
+            .query_row("SELECT title FROM tasks", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(title, "Write report");
+        let entries: i64 = conn
+            .query_row("SELECT COUNT(*) FROM time_entries", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(entries, 1);
+
+        let backups: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".bak"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0], "tasks.db.v0.20260304090000.bak");
+
+        // Running again is a no-op
+        migrate(&mut conn, &db_path, now()).unwrap();
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        drop(conn);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn databases_from_a_newer_taskquest_are_refused() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_version() + 1)
+            .unwrap();
+
+        let err = migrate(&mut conn, Path::new("tasks.db"), now()).unwrap_err();
+        assert!(err.to_string().contains("Please upgrade TaskQuest"));
+        assert_eq!(current_version(&conn).unwrap(), latest_version() + 1);
+    }
+}
@@ -1,5 +1,6 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Days, Duration, NaiveDate, NaiveTime, Utc, Weekday};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
@@ -10,9 +11,632 @@ pub struct Task {
     pub created_at: DateTime<Utc>,
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
-    pub paused_at: Option<DateTime<Utc>>,
-    pub total_pause_duration: i64, // seconds
     pub points_earned: i32,
+    #[serde(default)]
+    pub points_breakdown: Option<PointsBreakdown>, // None for tasks scored before breakdowns
+    #[serde(default)]
+    pub time_entries: Vec<TimeEntry>, // oldest first
+    #[serde(default)]
+    pub tags: Vec<String>, // lowercase, sorted
+    #[serde(default)]
+    pub project_id: Option<i64>,
+    #[serde(default)]
+    pub project: Option<String>, // name of project_id, for display
+    #[serde(default)]
+    pub parent_id: Option<i64>, // set for subtasks
+    #[serde(default)]
+    pub depends_on: Vec<i64>, // prerequisite task ids, ascending
+    #[serde(default)]
+    pub blocked_by: Vec<i64>, // prerequisites not completed yet
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>, // last day it counts as on time
+    #[serde(default)]
+    pub series_id: Option<i64>, // recurring series this is an instance of
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>, // rule of the series; None once it is stopped
+    #[serde(default)]
+    pub notes: String, // free-form, may span several lines
+    #[serde(default)]
+    pub estimate_minutes: Option<i64>, // expected active time
+}
+
+/// Everything needed to create a task
+#[derive(Debug, Clone)]
+pub struct NewTask {
+    pub title: String,
+    pub difficulty: i32,
+    pub tags: Vec<String>,
+    pub project: Option<Project>,
+    pub parent_id: Option<i64>,
+    pub depends_on: Vec<i64>,
+    pub due_date: Option<NaiveDate>,
+    pub recurrence: Option<Recurrence>, // starts a new series
+    pub series_id: Option<i64>,         // next instance of an existing series
+    pub notes: String,
+    pub estimate_minutes: Option<i64>,
+}
+
+impl NewTask {
+    pub fn new(title: String, difficulty: i32) -> Self {
+        Self {
+            title,
+            difficulty,
+            tags: Vec::new(),
+            project: None,
+            parent_id: None,
+            depends_on: Vec::new(),
+            due_date: None,
+            recurrence: None,
+            series_id: None,
+            notes: String::new(),
+            estimate_minutes: None,
+        }
+    }
+}
+
+impl Task {
+    /// Title followed by `#tag`s, the form the add/edit popup accepts
+    pub fn title_with_tags(&self) -> String {
+        let mut text = self.title.clone();
+        for tag in &self.tags {
+            text.push_str(" #");
+            text.push_str(tag);
+        }
+        text
+    }
+
+    /// Waiting on a prerequisite that is not completed yet
+    pub fn is_blocked(&self) -> bool {
+        self.status != TaskStatus::Completed && !self.blocked_by.is_empty()
+    }
+
+    /// Days left until the due date (negative once overdue)
+    pub fn days_until_due(&self, today: NaiveDate) -> Option<i64> {
+        self.due_date.map(|due| (due - today).num_days())
+    }
+
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        self.status != TaskStatus::Completed && self.days_until_due(today).is_some_and(|d| d < 0)
+    }
+
+    /// Seconds actually worked, counting a still-running interval up to `now`
+    pub fn active_seconds_at(&self, now: DateTime<Utc>) -> i64 {
+        self.time_entries.iter().map(|e| e.duration_at(now)).sum()
+    }
+
+    /// Active seconds of a finished task, if it was ever started
+    pub fn completed_active_seconds(&self) -> Option<i64> {
+        match (self.started_at, self.completed_at) {
+            (Some(_), Some(completed)) => Some(self.active_seconds_at(completed)),
+            _ => None,
+        }
+    }
+
+    /// Wall-clock seconds between first start and completion
+    pub fn elapsed_seconds(&self) -> Option<i64> {
+        match (self.started_at, self.completed_at) {
+            (Some(started), Some(completed)) => Some((completed - started).num_seconds()),
+            _ => None,
+        }
+    }
+
+    pub fn paused_at(&self) -> Option<DateTime<Utc>> {
+        if self.status != TaskStatus::Paused {
+            return None;
+        }
+        self.time_entries.last().and_then(|e| e.ended_at)
+    }
+
+    pub fn close_time_entry(&mut self, at: DateTime<Utc>) {
+        for entry in self.time_entries.iter_mut().filter(|e| e.ended_at.is_none()) {
+            entry.ended_at = Some(at);
+        }
+    }
+}
+
+/// Completion state of every task below a parent, at any depth
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct SubtaskProgress {
+    pub done: i32,
+    pub total: i32,
+    pub points: i64, // earned by completed subtasks
+}
+
+impl SubtaskProgress {
+    pub fn is_finished(&self) -> bool {
+        self.done == self.total
+    }
+
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.done as f64 / self.total as f64
+    }
+}
+
+/// Due date as typed in the add/edit popup or on the command line:
+/// `today`, `tomorrow`, a weekday (`fri`, `friday`: the next one, never
+/// today), an offset (`+3d`, `+2w`) or `YYYY-MM-DD`. Empty or `none`
+/// clears it.
+pub fn parse_due_date(input: &str, today: NaiveDate) -> Result<Option<NaiveDate>, String> {
+    let input = input.trim().to_lowercase();
+    let unknown = || {
+        format!(
+            "Unknown due date '{}' (try today, tomorrow, fri, +3d or {})",
+            input,
+            today.format("%Y-%m-%d")
+        )
+    };
+
+    let date = match input.as_str() {
+        "" | "none" => return Ok(None),
+        "today" | "tod" => today,
+        "tomorrow" | "tom" | "tmr" => today + Duration::days(1),
+        _ => {
+            if let Ok(weekday) = input.parse::<Weekday>() {
+                let ahead = (weekday.num_days_from_monday() as i64 - today.weekday().num_days_from_monday() as i64)
+                    .rem_euclid(7);
+                today + Duration::days(if ahead == 0 { 7 } else { ahead })
+            } else if let Some(offset) = input.strip_prefix('+') {
+                let (count, days_per_unit) = match (offset.strip_suffix('d'), offset.strip_suffix('w')) {
+                    (Some(count), _) => (count, 1),
+                    (_, Some(count)) => (count, 7),
+                    _ => return Err(unknown()),
+                };
+                let count: u64 = count.parse().map_err(|_| unknown())?;
+                count
+                    .checked_mul(days_per_unit)
+                    .and_then(|days| today.checked_add_days(Days::new(days)))
+                    .ok_or_else(unknown)?
+            } else {
+                NaiveDate::parse_from_str(&input, "%Y-%m-%d").map_err(|_| unknown())?
+            }
+        }
+    };
+    Ok(Some(date))
+}
+
+/// Short human description of a due date relative to `today`
+pub fn describe_due(due: NaiveDate, today: NaiveDate) -> String {
+    match (due - today).num_days() {
+        d if d < -1 => format!("overdue {}d", -d),
+        -1 => "overdue 1d".to_string(),
+        0 => "due today".to_string(),
+        1 => "due tomorrow".to_string(),
+        2..=6 => format!("due {}", due.format("%a")),
+        _ => format!("due {}", due.format("%b %-d")),
+    }
+}
+
+/// Longest `every N days` interval accepted, about ten years
+pub const MAX_REPEAT_DAYS: u32 = 3650;
+
+/// When a recurring task comes up again
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Recurrence {
+    Daily,
+    Weekdays,
+    Weekly(Vec<Weekday>), // Monday first, no duplicates, never empty
+    Monthly(u32),         // day of the month; clamped in shorter months
+    EveryNDays(u32),
+}
+
+impl Recurrence {
+    /// First occurrence strictly after `date`, or None past the last date
+    /// chrono can represent
+    pub fn next_after(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Recurrence::Daily => date.succ_opt(),
+            Recurrence::Weekdays => {
+                let mut next = date.succ_opt()?;
+                while matches!(next.weekday(), Weekday::Sat | Weekday::Sun) {
+                    next = next.succ_opt()?;
+                }
+                Some(next)
+            }
+            Recurrence::Weekly(days) => (1..=7)
+                .map_while(|offset| date.checked_add_days(Days::new(offset)))
+                .find(|d| days.contains(&d.weekday())),
+            Recurrence::Monthly(day) => {
+                let this_month = day_in_month(date.year(), date.month(), *day)?;
+                if this_month > date {
+                    Some(this_month)
+                } else if date.month() == 12 {
+                    day_in_month(date.year().checked_add(1)?, 1, *day)
+                } else {
+                    day_in_month(date.year(), date.month() + 1, *day)
+                }
+            }
+            Recurrence::EveryNDays(n) => date.checked_add_days(Days::new(*n as u64)),
+        }
+    }
+
+    /// First occurrence on or after `date`, the due date of a new series
+    pub fn first_on_or_after(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Recurrence::EveryNDays(_) => Some(date),
+            _ => self.next_after(date.pred_opt()?),
+        }
+    }
+
+    /// Occurrences from `first` (counted as one) up to and including `last`
+    pub fn count_between(&self, first: NaiveDate, last: NaiveDate) -> i32 {
+        let mut count = 0;
+        let mut date = Some(first);
+        while let Some(current) = date.filter(|d| *d <= last) {
+            count += 1;
+            date = self.next_after(current);
+        }
+        count
+    }
+}
+
+fn day_in_month(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    (1..=day).rev().find_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+}
+
+impl std::fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekdays => write!(f, "weekdays"),
+            Recurrence::Weekly(days) => {
+                let days: Vec<String> = days.iter().map(|d| d.to_string().to_lowercase()).collect();
+                write!(f, "weekly {}", days.join(","))
+            }
+            Recurrence::Monthly(day) => write!(f, "monthly {}", day),
+            Recurrence::EveryNDays(n) => write!(f, "every {} days", n),
+        }
+    }
+}
+
+/// Accepts the forms `Display` writes plus a few spellings people type:
+/// `every day`, `every 3d`, or just the weekdays (`mon,thu`)
+impl std::str::FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let unknown = || {
+            format!(
+                "Unknown repeat rule '{}' (try daily, weekdays, weekly mon,thu, monthly 15 or every 3 days)",
+                input.trim()
+            )
+        };
+        let too_long = || format!("Repeat interval can be at most {} days", MAX_REPEAT_DAYS);
+        let lower = input.to_lowercase();
+        let words: Vec<&str> = lower
+            .split(|c: char| c.is_whitespace() || c == ',' || c == ':')
+            .filter(|w| !w.is_empty())
+            .collect();
+        let weekdays = |words: &[&str]| -> Option<Vec<Weekday>> {
+            let mut days: Vec<Weekday> = words.iter().map(|w| w.parse().ok()).collect::<Option<_>>()?;
+            days.sort_by_key(|d| d.num_days_from_monday());
+            days.dedup();
+            (!days.is_empty()).then_some(days)
+        };
+
+        let rule = match words.as_slice() {
+            ["daily"] | ["every", "day"] => Recurrence::Daily,
+            ["weekdays"] | ["every", "weekday"] => Recurrence::Weekdays,
+            ["weekly", days @ ..] => Recurrence::Weekly(weekdays(days).ok_or_else(unknown)?),
+            ["monthly", day] => match day.parse() {
+                Ok(day @ 1..=31) => Recurrence::Monthly(day),
+                _ => return Err(unknown()),
+            },
+            ["every", count, "days" | "day" | "d"] => match count.parse() {
+                Ok(1) => Recurrence::Daily,
+                Ok(n) if n > MAX_REPEAT_DAYS => return Err(too_long()),
+                Ok(n) if n > 1 => Recurrence::EveryNDays(n),
+                _ => return Err(unknown()),
+            },
+            ["every", count] => match count.strip_suffix('d').and_then(|n| n.parse().ok()) {
+                Some(1) => Recurrence::Daily,
+                Some(n) if n > MAX_REPEAT_DAYS => return Err(too_long()),
+                Some(n) if n > 1 => Recurrence::EveryNDays(n),
+                _ => return Err(unknown()),
+            },
+            days => Recurrence::Weekly(weekdays(days).ok_or_else(unknown)?),
+        };
+        Ok(rule)
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(rule: Recurrence) -> Self {
+        rule.to_string()
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+
+    fn try_from(rule: String) -> Result<Self, Self::Error> {
+        rule.parse()
+    }
+}
+
+/// Repeat rule as typed in the add/edit popup or on the command line.
+/// Bare `weekly` and `monthly` repeat on `anchor`'s weekday or day of the
+/// month. Empty or `none` means the task does not repeat.
+pub fn parse_recurrence(input: &str, anchor: NaiveDate) -> Result<Option<Recurrence>, String> {
+    match input.trim().to_lowercase().as_str() {
+        "" | "none" => Ok(None),
+        "weekly" => Ok(Some(Recurrence::Weekly(vec![anchor.weekday()]))),
+        "monthly" => Ok(Some(Recurrence::Monthly(anchor.day()))),
+        rule => rule.parse().map(Some),
+    }
+}
+
+/// Longest estimate accepted, in minutes
+pub const MAX_ESTIMATE_MINUTES: i64 = 1000 * 60;
+
+/// Time estimate as typed: `30m`, `2h`, `1h30m`, `1.5h` or plain minutes
+/// (`90`), at most `MAX_ESTIMATE_MINUTES`. Empty clears it.
+pub fn parse_estimate(input: &str) -> Result<Option<i64>, String> {
+    let compact: String = input.split_whitespace().collect::<String>().to_lowercase();
+    if compact.is_empty() {
+        return Ok(None);
+    }
+    let invalid = || format!("Invalid estimate '{}' (try 30m, 2h or 1h30m)", input.trim());
+
+    let minutes = if compact.chars().all(|c| c.is_ascii_digit()) {
+        compact.parse::<i64>().map_err(|_| invalid())?
+    } else {
+        let mut total = 0.0;
+        let mut number = String::new();
+        for c in compact.chars() {
+            match c {
+                '0'..='9' | '.' => number.push(c),
+                'h' | 'm' => {
+                    let value: f64 = number.parse().map_err(|_| invalid())?;
+                    total += if c == 'h' { value * 60.0 } else { value };
+                    number.clear();
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        if !number.is_empty() {
+            return Err(invalid());
+        }
+        total.round() as i64
+    };
+
+    if minutes <= 0 {
+        return Err(invalid());
+    }
+    if minutes > MAX_ESTIMATE_MINUTES {
+        return Err(format!("Estimates can be at most {}", format_minutes(MAX_ESTIMATE_MINUTES)));
+    }
+    Ok(Some(minutes))
+}
+
+/// `45m`, `2h` or `1h 30m`
+pub fn format_minutes(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+/// Task ids as typed in the dependency field or on the command line:
+/// separated by commas or spaces, with an optional leading `#`
+pub fn parse_task_ids(input: &str) -> Result<Vec<i64>, String> {
+    let mut ids = Vec::new();
+    for part in input.split(|c: char| c == ',' || c.is_whitespace()).filter(|p| !p.is_empty()) {
+        let id = part
+            .trim_start_matches('#')
+            .parse::<i64>()
+            .map_err(|_| format!("'{}' is not a task id", part))?;
+        ids.push(id);
+    }
+    ids.sort_unstable();
+    ids.dedup();
+    Ok(ids)
+}
+
+pub fn format_task_ids(ids: &[i64]) -> String {
+    ids.iter().map(|id| format!("#{}", id)).collect::<Vec<_>>().join(", ")
+}
+
+/// A chain of prerequisites leading from `from` to `to`, if there is one.
+/// `graph` maps each task to the tasks it depends on.
+pub fn dependency_path(graph: &HashMap<i64, Vec<i64>>, from: i64, to: i64) -> Option<Vec<i64>> {
+    let mut came_from: HashMap<i64, i64> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    let mut seen = HashSet::from([from]);
+
+    while let Some(id) = queue.pop_front() {
+        if id == to {
+            let mut path = vec![to];
+            let mut cursor = to;
+            while let Some(prev) = came_from.get(&cursor) {
+                path.push(*prev);
+                cursor = *prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for next in graph.get(&id).into_iter().flatten() {
+            if seen.insert(*next) {
+                came_from.insert(*next, id);
+                queue.push_back(*next);
+            }
+        }
+    }
+    None
+}
+
+/// A task placed in the tree: how deeply it is nested and whether any of
+/// its children are listed too
+pub struct TaskRow<'a> {
+    pub task: &'a Task,
+    pub depth: usize,
+    pub has_children: bool,
+}
+
+/// Order tasks depth-first so each subtask follows its parent. Top-level
+/// tasks keep their order, subtasks are listed oldest first, and tasks whose
+/// parent is not in `tasks` become top-level. Children of `collapsed` tasks
+/// are left out.
+pub fn build_tree<'a>(tasks: &[&'a Task], collapsed: &HashSet<i64>) -> Vec<TaskRow<'a>> {
+    let ids: HashSet<i64> = tasks.iter().map(|t| t.id).collect();
+    let mut children: HashMap<i64, Vec<&'a Task>> = HashMap::new();
+    let mut roots = Vec::new();
+    for task in tasks {
+        match task.parent_id.filter(|id| ids.contains(id)) {
+            Some(parent) => children.entry(parent).or_default().push(task),
+            None => roots.push(*task),
+        }
+    }
+    for siblings in children.values_mut() {
+        siblings.sort_by_key(|t| (t.created_at, t.id));
+    }
+
+    fn visit<'a>(
+        task: &'a Task,
+        depth: usize,
+        children: &HashMap<i64, Vec<&'a Task>>,
+        collapsed: &HashSet<i64>,
+        rows: &mut Vec<TaskRow<'a>>,
+    ) {
+        let kids = children.get(&task.id);
+        rows.push(TaskRow {
+            task,
+            depth,
+            has_children: kids.is_some(),
+        });
+        if collapsed.contains(&task.id) {
+            return;
+        }
+        for child in kids.into_iter().flatten() {
+            visit(child, depth + 1, children, collapsed, rows);
+        }
+    }
+
+    let mut rows = Vec::with_capacity(tasks.len());
+    for root in roots {
+        visit(root, 0, &children, collapsed, &mut rows);
+    }
+    rows
+}
+
+/// Put around matched words in [`SearchHit`] text
+pub const MATCH_START: char = '\u{1}';
+pub const MATCH_END: char = '\u{2}';
+
+/// A task found by full-text search
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub task: Task,
+    /// The title with matched words between MATCH_START and MATCH_END
+    pub title: String,
+    /// An excerpt of the notes around the match, when the notes matched
+    pub notes: Option<String>,
+}
+
+/// Split marked search text into (text, is_match) pieces
+pub fn match_segments(marked: &str) -> Vec<(&str, bool)> {
+    let mut segments = Vec::new();
+    let mut in_match = false;
+    for piece in marked.split([MATCH_START, MATCH_END]) {
+        if !piece.is_empty() {
+            segments.push((piece, in_match));
+        }
+        in_match = !in_match;
+    }
+    segments
+}
+
+/// Where a fuzzy pattern matched and how well; higher scores are better
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Char indices of the matched characters in the text
+    pub positions: Vec<usize>,
+}
+
+/// Match `pattern` as a case-insensitive subsequence of `text` (spaces in
+/// the pattern are ignored). Of the possible alignments the tightest one
+/// ending earliest is scored: consecutive characters and word starts earn
+/// bonuses, gaps cost points.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let pattern: Vec<char> = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch { score: 0, positions: Vec::new() });
+    }
+    let chars: Vec<char> = text.chars().collect();
+    // One char per char so indices line up with `chars`
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+
+    // Forward: the earliest position the whole pattern is seen by
+    let mut matched = 0;
+    let mut end = 0;
+    for (i, c) in lower.iter().enumerate() {
+        if *c == pattern[matched] {
+            matched += 1;
+            if matched == pattern.len() {
+                end = i;
+                break;
+            }
+        }
+    }
+    if matched < pattern.len() {
+        return None;
+    }
+
+    // Backward from there: the latest start, so the match is as tight as possible
+    let mut positions = vec![0; pattern.len()];
+    let mut remaining = pattern.len();
+    for i in (0..=end).rev() {
+        if lower[i] == pattern[remaining - 1] {
+            remaining -= 1;
+            positions[remaining] = i;
+            if remaining == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut score = 0;
+    for (n, &i) in positions.iter().enumerate() {
+        score += 16;
+        let word_start = i == 0
+            || !chars[i - 1].is_alphanumeric()
+            || (chars[i - 1].is_lowercase() && chars[i].is_uppercase());
+        if word_start {
+            score += 8;
+        }
+        if n > 0 {
+            let gap = (i - positions[n - 1] - 1) as i64;
+            score += if gap == 0 { 12 } else { -gap.min(8) };
+        }
+    }
+    // Prefer matches near the start of the text
+    score -= (positions[0] as i64).min(8);
+    Some(FuzzyMatch { score, positions })
+}
+
+/// One uninterrupted work interval on a task (start/resume until pause/complete)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub id: i64,
+    pub task_id: i64,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>, // None while the interval is running
+}
+
+impl TimeEntry {
+    pub fn duration_at(&self, now: DateTime<Utc>) -> i64 {
+        let end = self.ended_at.unwrap_or(now);
+        (end - self.started_at).num_seconds().max(0)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -34,6 +658,161 @@ impl TaskStatus {
     }
 }
 
+/// Kinds of entries in the append-only task history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskEventKind {
+    Created,
+    Edited,
+    Started,
+    Paused,
+    Resumed,
+    Completed,
+    Deleted,
+    Undone,
+    Redone,
+}
+
+impl TaskEventKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TaskEventKind::Created => "Created",
+            TaskEventKind::Edited => "Edited",
+            TaskEventKind::Started => "Started",
+            TaskEventKind::Paused => "Paused",
+            TaskEventKind::Resumed => "Resumed",
+            TaskEventKind::Completed => "Completed",
+            TaskEventKind::Deleted => "Deleted",
+            TaskEventKind::Undone => "Undone",
+            TaskEventKind::Redone => "Redone",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskEvent {
+    pub id: i64,
+    pub task_id: i64,
+    pub kind: TaskEventKind,
+    pub occurred_at: DateTime<Utc>,
+    pub detail: Option<String>,
+}
+
+/// Why a ledger row granted points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PointReason {
+    TaskBase,
+    TimeBonus,
+    StreakMultiplier,
+    FocusBonus,
+    AccuracyBonus,
+    OnTimeBonus,
+    MinimumFloor,
+    Milestone,
+    Imported, // totals carried over from before the ledger existed
+}
+
+impl PointReason {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PointReason::TaskBase => "Task base",
+            PointReason::TimeBonus => "Time bonus",
+            PointReason::StreakMultiplier => "Streak multiplier",
+            PointReason::FocusBonus => "Focus bonus",
+            PointReason::AccuracyBonus => "Accuracy bonus",
+            PointReason::OnTimeBonus => "On-time bonus",
+            PointReason::MinimumFloor => "Minimum floor",
+            PointReason::Milestone => "Milestone",
+            PointReason::Imported => "Imported",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PointGrant {
+    pub reason: PointReason,
+    pub points: i32,
+}
+
+/// How a task's points were calculated, persisted alongside the task
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PointsBreakdown {
+    pub base: i32,
+    pub time_bonus: i32,
+    pub streak_multiplier: f32, // from the current streak, before the bonus pool
+    pub bonus_pool: f32,
+    pub streak_bonus: i32, // points added (or removed) by multiplier × pool
+    pub focus_bonus: i32,
+    #[serde(default)]
+    pub accuracy_bonus: i32, // estimate-accuracy strategy only
+    #[serde(default)]
+    pub on_time_bonus: i32, // finished by the due date
+    pub floor_adjustment: i32, // top-up to the 50%-of-base minimum
+    pub total: i32,
+}
+
+impl PointsBreakdown {
+    /// All-zero breakdown with neutral multipliers, for strategies to fill in
+    pub fn flat() -> Self {
+        Self {
+            base: 0,
+            time_bonus: 0,
+            streak_multiplier: 1.0,
+            bonus_pool: 1.0,
+            streak_bonus: 0,
+            focus_bonus: 0,
+            accuracy_bonus: 0,
+            on_time_bonus: 0,
+            floor_adjustment: 0,
+            total: 0,
+        }
+    }
+
+    /// One ledger grant per non-zero component (base is always recorded)
+    pub fn grants(&self) -> Vec<PointGrant> {
+        [
+            (PointReason::TaskBase, self.base),
+            (PointReason::TimeBonus, self.time_bonus),
+            (PointReason::StreakMultiplier, self.streak_bonus),
+            (PointReason::FocusBonus, self.focus_bonus),
+            (PointReason::AccuracyBonus, self.accuracy_bonus),
+            (PointReason::OnTimeBonus, self.on_time_bonus),
+            (PointReason::MinimumFloor, self.floor_adjustment),
+        ]
+        .into_iter()
+        .filter(|(reason, points)| *points != 0 || *reason == PointReason::TaskBase)
+        .map(|(reason, points)| PointGrant { reason, points })
+        .collect()
+    }
+
+    /// Compact one-line explanation, e.g. "50 base +12 time +5 streak"
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("{} base", self.base)];
+        for (label, points) in [
+            ("time", self.time_bonus),
+            ("streak", self.streak_bonus),
+            ("focus", self.focus_bonus),
+            ("accuracy", self.accuracy_bonus),
+            ("on-time", self.on_time_bonus),
+            ("floor", self.floor_adjustment),
+        ] {
+            if points != 0 {
+                parts.push(format!("{:+} {}", points, label));
+            }
+        }
+        parts.join(" ")
+    }
+}
+
+/// One row of the append-style points ledger; totals are the sum of these
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub id: i64,
+    pub task_id: Option<i64>,
+    pub reason: PointReason,
+    pub points: i32,
+    pub created_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserStats {
     pub total_points: i32,
@@ -68,6 +847,220 @@ pub struct DailyStats {
     pub focus_time: i64, // seconds
 }
 
+impl DailyStats {
+    pub fn empty(day: NaiveDate) -> Self {
+        Self {
+            date: day.and_time(NaiveTime::MIN).and_utc(),
+            tasks_completed: 0,
+            points_earned: 0,
+            focus_time: 0,
+        }
+    }
+}
+
+/// Completed-task totals for one tag
+#[derive(Debug, Clone, Serialize)]
+pub struct TagStats {
+    pub tag: String,
+    pub tasks_completed: i32,
+    pub points_earned: i64,
+    pub focus_time: i64, // seconds
+}
+
+/// A completed task that had an estimate, for estimation analytics
+#[derive(Debug, Clone, Serialize)]
+pub struct EstimateSample {
+    pub task_id: i64,
+    pub completed_at: DateTime<Utc>,
+    pub difficulty: i32,
+    pub estimate_minutes: i64,
+    pub actual_seconds: i64,
+}
+
+impl EstimateSample {
+    /// Relative error: 0.5 took half as long again as estimated, -0.25
+    /// finished a quarter early
+    pub fn error(&self) -> f64 {
+        self.actual_seconds as f64 / self.estimate_minutes.saturating_mul(60) as f64 - 1.0
+    }
+}
+
+/// How far off the estimates for one difficulty level were
+#[derive(Debug, Clone, Serialize)]
+pub struct EstimateAccuracy {
+    pub difficulty: i32,
+    pub tasks: i32,
+    pub mean_error: f64,     // signed: positive means underestimated
+    pub mean_abs_error: f64, // how far off, either way
+}
+
+pub fn estimate_accuracy_by_difficulty(samples: &[EstimateSample]) -> Vec<EstimateAccuracy> {
+    let mut by_difficulty: BTreeMap<i32, Vec<f64>> = BTreeMap::new();
+    for sample in samples {
+        by_difficulty.entry(sample.difficulty).or_default().push(sample.error());
+    }
+    by_difficulty
+        .into_iter()
+        .map(|(difficulty, errors)| {
+            let n = errors.len() as f64;
+            EstimateAccuracy {
+                difficulty,
+                tasks: errors.len() as i32,
+                mean_error: errors.iter().sum::<f64>() / n,
+                mean_abs_error: errors.iter().map(|e| e.abs()).sum::<f64>() / n,
+            }
+        })
+        .collect()
+}
+
+/// Levels with fewer tracked tasks than this are reported but not flagged
+pub const MIN_CALIBRATION_TASKS: usize = 3;
+
+/// How long tasks of one difficulty level actually took
+#[derive(Debug, Clone, Serialize)]
+pub struct DifficultyCalibration {
+    pub difficulty: i32,
+    pub tasks: usize,
+    pub mean_seconds: f64,
+    pub median_seconds: f64,
+    pub p25_seconds: f64,
+    pub p75_seconds: f64,
+    /// Other levels whose middle half of completion times overlaps this one's
+    pub overlaps_with: Vec<i32>,
+    /// A lower level whose tasks take at least as long as this one's
+    pub not_slower_than: Option<i32>,
+}
+
+impl DifficultyCalibration {
+    /// Enough tasks for the quartiles to mean something
+    pub fn is_reliable(&self) -> bool {
+        self.tasks >= MIN_CALIBRATION_TASKS
+    }
+}
+
+/// Completion-time distribution per difficulty from (difficulty, active
+/// seconds) pairs, with levels that can't be told apart flagged
+pub fn calibrate_difficulties(samples: &[(i32, i64)]) -> Vec<DifficultyCalibration> {
+    let mut by_difficulty: BTreeMap<i32, Vec<f64>> = BTreeMap::new();
+    for (difficulty, seconds) in samples {
+        by_difficulty.entry(*difficulty).or_default().push(*seconds as f64);
+    }
+
+    let mut levels: Vec<DifficultyCalibration> = by_difficulty
+        .into_iter()
+        .map(|(difficulty, mut times)| {
+            times.sort_by(|a, b| a.total_cmp(b));
+            DifficultyCalibration {
+                difficulty,
+                tasks: times.len(),
+                mean_seconds: times.iter().sum::<f64>() / times.len() as f64,
+                median_seconds: percentile(&times, 0.5),
+                p25_seconds: percentile(&times, 0.25),
+                p75_seconds: percentile(&times, 0.75),
+                overlaps_with: Vec::new(),
+                not_slower_than: None,
+            }
+        })
+        .collect();
+
+    let reliable: Vec<(i32, f64, f64, f64)> = levels
+        .iter()
+        .filter(|level| level.is_reliable())
+        .map(|level| (level.difficulty, level.p25_seconds, level.median_seconds, level.p75_seconds))
+        .collect();
+    for level in levels.iter_mut().filter(|level| level.is_reliable()) {
+        level.overlaps_with = reliable
+            .iter()
+            .filter(|(other, p25, _, p75)| {
+                *other != level.difficulty && *p25 <= level.p75_seconds && level.p25_seconds <= *p75
+            })
+            .map(|(other, ..)| *other)
+            .collect();
+        // The highest lower level that takes at least as long
+        level.not_slower_than = reliable
+            .iter()
+            .filter(|(other, _, median, _)| *other < level.difficulty && *median >= level.median_seconds)
+            .map(|(other, ..)| *other)
+            .next_back();
+    }
+    levels
+}
+
+/// Linear interpolation between the closest ranks of a sorted slice
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p * (sorted.len() - 1) as f64;
+    let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64)
+}
+
+/// The difficulty whose average completion time is closest to `seconds`,
+/// compared by ratio so 10m vs 20m counts as far apart as 1h vs 2h
+pub fn suggest_difficulty(avg_times: &[(i32, f64)], seconds: f64) -> Option<i32> {
+    if seconds <= 0.0 {
+        return None;
+    }
+    avg_times
+        .iter()
+        .filter(|(_, avg)| *avg > 0.0)
+        .min_by(|(_, a), (_, b)| {
+            (a / seconds).ln().abs().total_cmp(&(b / seconds).ln().abs())
+        })
+        .map(|(difficulty, _)| *difficulty)
+}
+
+/// How reliably the instances of one recurring series get done
+#[derive(Debug, Clone, Serialize)]
+pub struct SeriesStats {
+    pub series_id: i64,
+    pub title: String, // of the latest instance
+    pub rule: Recurrence,
+    pub active: bool,
+    pub instances_completed: i32,
+    pub occurrences: i32, // due dates that have come up so far
+    pub completion_rate: Option<f64>, // None until the first due date comes up
+    pub points_earned: i64,
+}
+
+/// A named group of tasks with its own points and focus time
+#[derive(Debug, Clone, Serialize)]
+pub struct Project {
+    pub id: i64,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Completed-task totals for one project
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProjectStats {
+    pub tasks_completed: i32,
+    pub points_earned: i64,
+    pub focus_time: i64, // seconds
+}
+
+/// Split `#tag` words out of task input. Returns the remaining title and
+/// the tags, lowercased, sorted and without duplicates. A tag needs at least
+/// one letter, so task references like `#123` stay in the title.
+pub fn split_tags(input: &str) -> (String, Vec<String>) {
+    let mut title_words = Vec::new();
+    let mut tags = Vec::new();
+
+    for word in input.split_whitespace() {
+        match word.strip_prefix('#') {
+            Some(tag)
+                if tag.chars().any(char::is_alphabetic)
+                    && tag.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') =>
+            {
+                tags.push(tag.to_lowercase());
+            }
+            _ => title_words.push(word),
+        }
+    }
+
+    tags.sort();
+    tags.dedup();
+    (title_words.join(" "), tags)
+}
+
 // Reserved for future use - comprehensive performance analytics
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
@@ -77,3 +1070,166 @@ pub struct PerformanceMetrics {
     pub focus_score: f32, // 0-100, based on task interruptions
     pub efficiency_score: f32, // 0-100, based on time vs. expected time
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn due_dates_resolve_relative_to_today() {
+        let today = date(2026, 3, 4); // a Wednesday
+        let due = |input: &str| parse_due_date(input, today).unwrap();
+
+        assert_eq!(due(""), None);
+        assert_eq!(due("none"), None);
+        assert_eq!(due("today"), Some(today));
+        assert_eq!(due("tom"), Some(date(2026, 3, 5)));
+        assert_eq!(due("fri"), Some(date(2026, 3, 6)));
+        assert_eq!(due("wed"), Some(date(2026, 3, 11))); // a week out, never today
+        assert_eq!(due("+3d"), Some(date(2026, 3, 7)));
+        assert_eq!(due(" +2W "), Some(date(2026, 3, 18)));
+        assert_eq!(due("2026-12-25"), Some(date(2026, 12, 25)));
+    }
+
+    #[test]
+    fn bad_or_overflowing_due_dates_are_rejected() {
+        let today = date(2026, 3, 4);
+        for input in ["+3é", "+é", "+d", "+3x", "+-3d", "+999999999999d", "+99999999999999999w", "soon", "2026-02-30"] {
+            let err = parse_due_date(input, today).unwrap_err();
+            assert!(err.starts_with("Unknown due date"), "{}: {}", input, err);
+        }
+    }
+
+    #[test]
+    fn monthly_on_the_31st_clamps_to_short_months() {
+        let rule = Recurrence::Monthly(31);
+        assert_eq!(rule.next_after(date(2026, 1, 15)), Some(date(2026, 1, 31)));
+        assert_eq!(rule.next_after(date(2026, 1, 31)), Some(date(2026, 2, 28)));
+        assert_eq!(rule.next_after(date(2028, 1, 31)), Some(date(2028, 2, 29)));
+        assert_eq!(rule.next_after(date(2026, 2, 28)), Some(date(2026, 3, 31)));
+        assert_eq!(rule.next_after(date(2026, 12, 31)), Some(date(2027, 1, 31)));
+    }
+
+    #[test]
+    fn weekdays_skip_the_weekend() {
+        let rule = Recurrence::Weekdays;
+        assert_eq!(rule.next_after(date(2026, 3, 5)), Some(date(2026, 3, 6))); // Thu → Fri
+        assert_eq!(rule.next_after(date(2026, 3, 6)), Some(date(2026, 3, 9))); // Fri → Mon
+        assert_eq!(rule.next_after(date(2026, 3, 7)), Some(date(2026, 3, 9))); // Sat → Mon
+        assert_eq!(rule.first_on_or_after(date(2026, 3, 8)), Some(date(2026, 3, 9)));
+        assert_eq!(rule.first_on_or_after(date(2026, 3, 9)), Some(date(2026, 3, 9)));
+        assert_eq!(rule.count_between(date(2026, 3, 6), date(2026, 3, 13)), 6);
+    }
+
+    #[test]
+    fn weekly_and_interval_rules() {
+        let weekly = Recurrence::Weekly(vec![Weekday::Mon, Weekday::Thu]);
+        assert_eq!(weekly.next_after(date(2026, 3, 4)), Some(date(2026, 3, 5)));
+        assert_eq!(weekly.next_after(date(2026, 3, 5)), Some(date(2026, 3, 9)));
+
+        let every = Recurrence::EveryNDays(MAX_REPEAT_DAYS);
+        assert_eq!(every.next_after(date(2026, 3, 4)), date(2026, 3, 4).checked_add_days(Days::new(3650)));
+        assert_eq!(every.first_on_or_after(date(2026, 3, 4)), Some(date(2026, 3, 4)));
+        assert_eq!(every.next_after(NaiveDate::MAX), None);
+        assert_eq!(Recurrence::Daily.next_after(NaiveDate::MAX), None);
+    }
+
+    #[test]
+    fn repeat_rules_parse_and_round_trip() {
+        let parse = |input: &str| input.parse::<Recurrence>();
+
+        assert_eq!(parse("every day"), Ok(Recurrence::Daily));
+        assert_eq!(parse("every 1 day"), Ok(Recurrence::Daily));
+        assert_eq!(parse("every 3d"), Ok(Recurrence::EveryNDays(3)));
+        assert_eq!(parse("Every 3650 days"), Ok(Recurrence::EveryNDays(3650)));
+        assert_eq!(parse("thu, mon, thu"), Ok(Recurrence::Weekly(vec![Weekday::Mon, Weekday::Thu])));
+        assert_eq!(parse("monthly 31"), Ok(Recurrence::Monthly(31)));
+        for rule in [
+            Recurrence::Daily,
+            Recurrence::Weekdays,
+            Recurrence::Weekly(vec![Weekday::Tue, Weekday::Sat]),
+            Recurrence::Monthly(15),
+            Recurrence::EveryNDays(10),
+        ] {
+            assert_eq!(parse(&rule.to_string()), Ok(rule));
+        }
+
+        assert!(parse("every 3651 days").unwrap_err().contains("at most 3650 days"));
+        assert!(parse("every 4000000000d").unwrap_err().contains("at most 3650 days"));
+        for input in ["every 0 days", "every 99999999999 days", "monthly 32", "weekly", "sometimes"] {
+            assert!(parse(input).unwrap_err().starts_with("Unknown repeat rule"), "{}", input);
+        }
+    }
+
+    #[test]
+    fn estimates_accept_minutes_hours_and_mixes() {
+        let minutes = |input: &str| parse_estimate(input).unwrap();
+
+        assert_eq!(minutes(""), None);
+        assert_eq!(minutes("90"), Some(90));
+        assert_eq!(minutes("45m"), Some(45));
+        assert_eq!(minutes("2H"), Some(120));
+        assert_eq!(minutes("1h 30m"), Some(90));
+        assert_eq!(minutes("1.5h"), Some(90));
+        assert_eq!(minutes("1000h"), Some(MAX_ESTIMATE_MINUTES));
+        assert_eq!(format_minutes(90), "1h 30m");
+    }
+
+    #[test]
+    fn bad_or_oversized_estimates_are_rejected() {
+        for input in ["0", "0m", "abc", "30s", "h", "1h30", "1..5h", "-5m"] {
+            assert!(parse_estimate(input).unwrap_err().starts_with("Invalid estimate"), "{}", input);
+        }
+        for input in ["1001h", "60001", "9223372036854775807", "99999999999999999999h"] {
+            assert_eq!(parse_estimate(input), Err("Estimates can be at most 1000h".to_string()), "{}", input);
+        }
+    }
+
+    #[test]
+    fn fuzzy_match_finds_the_tightest_subsequence() {
+        let positions = |pattern: &str, text: &str| fuzzy_match(pattern, text).map(|m| m.positions);
+
+        assert_eq!(fuzzy_match("", "anything"), Some(FuzzyMatch { score: 0, positions: Vec::new() }));
+        assert_eq!(positions("wrp", "Write report"), Some(vec![0, 6, 8]));
+        assert_eq!(positions("W  P", "write report"), Some(vec![0, 8]));
+        assert_eq!(positions("fb", "fooBar"), Some(vec![0, 3]));
+        assert_eq!(positions("fö", "Café föé"), Some(vec![5, 6])); // char indices, not bytes
+        assert_eq!(positions("pw", "Write report"), None);
+        assert_eq!(positions("xyz", "Write report"), None);
+    }
+
+    #[test]
+    fn fuzzy_match_prefers_runs_and_word_starts() {
+        let score = |pattern: &str, text: &str| fuzzy_match(pattern, text).unwrap().score;
+
+        assert!(score("rep", "Report") > score("rep", "rXeXp"));
+        assert!(score("rep", "the report") > score("rep", "prepare"));
+        assert!(score("b", "Build") > score("b", "ebb"));
+    }
+
+    #[test]
+    fn dependency_path_follows_prerequisites() {
+        // 1 depends on 2, 2 on 3 and 4
+        let graph = HashMap::from([(1, vec![2]), (2, vec![3, 4])]);
+
+        assert_eq!(dependency_path(&graph, 1, 4), Some(vec![1, 2, 4]));
+        assert_eq!(dependency_path(&graph, 3, 3), Some(vec![3]));
+        assert_eq!(dependency_path(&graph, 4, 1), None);
+        assert_eq!(dependency_path(&graph, 1, 99), None);
+    }
+
+    #[test]
+    fn tags_need_a_letter() {
+        let (title, tags) = split_tags("Follow up on #123 #Home #2026-goals #12-3 #home");
+        assert_eq!(title, "Follow up on #123 #12-3");
+        assert_eq!(tags, vec!["2026-goals", "home"]);
+
+        let (title, tags) = split_tags("Fix # and #_ later");
+        assert_eq!(title, "Fix # and #_ later");
+        assert!(tags.is_empty());
+    }
+}
@@ -0,0 +1,132 @@
+use crate::models::{PointGrant, PointsBreakdown, Task, UserStats};
+use crate::scoring::ScoringStrategy;
+use std::collections::HashMap;
+
+/// New score for one completed task
+#[derive(Debug, Clone)]
+pub struct TaskRescore {
+    pub task: Task, // carries the new points_earned and breakdown
+    pub old_points: i32,
+    pub breakdown: PointsBreakdown,
+    pub milestones: Vec<PointGrant>,
+}
+
+/// Result of replaying the completion history, ready to be written back
+#[derive(Debug, Clone)]
+pub struct Recompute {
+    pub before: UserStats,
+    pub after: UserStats,
+    pub tasks: Vec<TaskRescore>,
+}
+
+/// Replay completed tasks (oldest first) through `strategy` as if each had
+/// just been completed, using only the history that existed at that moment
+/// for time averages.
+pub fn replay(history: &[Task], before: UserStats, strategy: &dyn ScoringStrategy) -> Recompute {
+    let mut stats = UserStats::default();
+    let mut durations: HashMap<i32, (f64, u32)> = HashMap::new(); // difficulty -> (sum, count)
+    let mut tasks = Vec::with_capacity(history.len());
+
+    for task in history {
+        let avg_time = durations
+            .get(&task.difficulty)
+            .map(|(sum, count)| sum / *count as f64);
+
+        let breakdown = strategy.calculate_task_points(task, &stats, avg_time);
+        let milestones = strategy.update_stats_on_completion(&mut stats, task, breakdown.total);
+
+        if let Some(active_time) = task.completed_active_seconds() {
+            let entry = durations.entry(task.difficulty).or_insert((0.0, 0));
+            entry.0 += active_time as f64;
+            entry.1 += 1;
+        }
+
+        let mut rescored = task.clone();
+        rescored.points_earned = breakdown.total;
+        rescored.points_breakdown = Some(breakdown);
+
+        tasks.push(TaskRescore {
+            task: rescored,
+            old_points: task.points_earned,
+            breakdown,
+            milestones,
+        });
+    }
+
+    Recompute {
+        before,
+        after: stats,
+        tasks,
+    }
+}
+
+impl Recompute {
+    pub fn changed_tasks(&self) -> impl Iterator<Item = &TaskRescore> {
+        self.tasks.iter().filter(|r| r.task.points_earned != r.old_points)
+    }
+
+    /// True when applying would leave points and stats as they are
+    pub fn is_unchanged(&self) -> bool {
+        self.changed_tasks().next().is_none() && stats_lines(&self.before, &self.after).is_empty()
+    }
+
+    /// Before/after comparison: changed stats first, then every task whose
+    /// points move
+    pub fn diff_lines(&self) -> Vec<String> {
+        let mut lines = stats_lines(&self.before, &self.after);
+
+        let changed: Vec<&TaskRescore> = self.changed_tasks().collect();
+        if !changed.is_empty() {
+            lines.push(String::new());
+            lines.push(format!("{} of {} completed tasks change:", changed.len(), self.tasks.len()));
+            for rescore in changed {
+                lines.push(format!(
+                    "  #{} {}: {} → {} ({:+})",
+                    rescore.task.id,
+                    rescore.task.title,
+                    rescore.old_points,
+                    rescore.task.points_earned,
+                    rescore.task.points_earned - rescore.old_points
+                ));
+            }
+        }
+
+        lines
+    }
+}
+
+fn stats_lines(before: &UserStats, after: &UserStats) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut compare = |label: &str, old: i64, new: i64| {
+        if old != new {
+            lines.push(format!("{:<16} {} → {} ({:+})", label, old, new, new - old));
+        }
+    };
+
+    compare("Total points", before.total_points as i64, after.total_points as i64);
+    compare("Tasks completed", before.tasks_completed as i64, after.tasks_completed as i64);
+    compare("Current streak", before.current_streak as i64, after.current_streak as i64);
+    compare("Longest streak", before.longest_streak as i64, after.longest_streak as i64);
+    compare("Focus minutes", before.total_focus_time / 60, after.total_focus_time / 60);
+
+    if (before.streak_bonus_pool - after.streak_bonus_pool).abs() > 0.005 {
+        lines.push(format!(
+            "{:<16} {:.2} → {:.2}",
+            "Bonus pool", before.streak_bonus_pool, after.streak_bonus_pool
+        ));
+    }
+    if before.last_completion_date != after.last_completion_date {
+        let show = |d: Option<chrono::DateTime<chrono::Utc>>| {
+            d.map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "never".to_string())
+        };
+        lines.push(format!(
+            "{:<16} {} → {}",
+            "Last completion",
+            show(before.last_completion_date),
+            show(after.last_completion_date)
+        ));
+    }
+
+    lines
+}
@@ -1,44 +1,270 @@
-use crate::models::{Task, UserStats};
-use chrono::Utc;
+use crate::clock::DayBoundary;
+use crate::config::{ScoringRules, StrategyKind};
+use crate::models::{PointGrant, PointReason, PointsBreakdown, Task, TaskStatus, UserStats};
+use chrono::{DateTime, Utc};
 
-const BASE_POINTS_MULTIPLIER: i32 = 10;
-const MILESTONE_THRESHOLDS: &[i32] = &[10, 25, 50, 100, 250, 500];
-const MILESTONE_BONUS: i32 = 100;
+/// A complete scoring game: how a completed task is scored, how stats move
+/// afterwards, and how the Stats view grades recent work
+pub trait ScoringStrategy {
+    fn kind(&self) -> StrategyKind;
+
+    fn rules(&self) -> &ScoringRules;
+
+    fn day_boundary(&self) -> &DayBoundary;
+
+    fn calculate_task_points(
+        &self,
+        task: &Task,
+        user_stats: &UserStats,
+        avg_time_for_difficulty: Option<f64>,
+    ) -> PointsBreakdown;
+
+    /// Update user stats after completing a task. Returns any milestone
+    /// bonuses earned so they can be recorded in the ledger.
+    fn update_stats_on_completion(&self, stats: &mut UserStats, task: &Task, points: i32) -> Vec<PointGrant> {
+        record_completion(stats, task, points, self.rules(), self.day_boundary());
+        award_milestones(stats, self.rules())
+    }
+
+    fn focus_score(&self, tasks: &[Task]) -> f32 {
+        calculate_focus_score(tasks)
+    }
+
+    fn efficiency_score(&self, tasks: &[Task], avg_times: &[(i32, f64)]) -> f32 {
+        calculate_efficiency_score(tasks, avg_times)
+    }
+}
+
+pub fn build_strategy(kind: StrategyKind, rules: ScoringRules, days: DayBoundary) -> Box<dyn ScoringStrategy> {
+    match kind {
+        StrategyKind::Classic => Box::new(Classic { rules, days }),
+        StrategyKind::DifficultyOnly => Box::new(DifficultyOnly { rules, days }),
+        StrategyKind::EstimateAccuracy => Box::new(EstimateAccuracy { rules, days }),
+    }
+}
+
+/// Difficulty, speed, streak and focus all count
+pub struct Classic {
+    rules: ScoringRules,
+    days: DayBoundary,
+}
+
+impl ScoringStrategy for Classic {
+    fn kind(&self) -> StrategyKind {
+        StrategyKind::Classic
+    }
+
+    fn rules(&self) -> &ScoringRules {
+        &self.rules
+    }
+
+    fn day_boundary(&self) -> &DayBoundary {
+        &self.days
+    }
+
+    fn calculate_task_points(
+        &self,
+        task: &Task,
+        user_stats: &UserStats,
+        avg_time_for_difficulty: Option<f64>,
+    ) -> PointsBreakdown {
+        calculate_classic_points(task, user_stats, avg_time_for_difficulty, &self.rules, &self.days)
+    }
+}
+
+/// Flat points per difficulty level; no bonuses, multipliers or milestones
+pub struct DifficultyOnly {
+    rules: ScoringRules,
+    days: DayBoundary,
+}
+
+impl ScoringStrategy for DifficultyOnly {
+    fn kind(&self) -> StrategyKind {
+        StrategyKind::DifficultyOnly
+    }
+
+    fn rules(&self) -> &ScoringRules {
+        &self.rules
+    }
+
+    fn day_boundary(&self) -> &DayBoundary {
+        &self.days
+    }
+
+    fn calculate_task_points(&self, task: &Task, _user_stats: &UserStats, _avg: Option<f64>) -> PointsBreakdown {
+        let base_points = task.difficulty * self.rules.base_points_multiplier;
+        PointsBreakdown {
+            base: base_points,
+            total: base_points,
+            ..PointsBreakdown::flat()
+        }
+    }
+
+    fn update_stats_on_completion(&self, stats: &mut UserStats, task: &Task, points: i32) -> Vec<PointGrant> {
+        record_completion(stats, task, points, &self.rules, &self.days);
+        Vec::new()
+    }
+}
+
+/// Rewards finishing close to the expected time rather than finishing fast.
+/// The expectation is the task's own estimate, or the historical average for
+/// its difficulty when it has none.
+pub struct EstimateAccuracy {
+    rules: ScoringRules,
+    days: DayBoundary,
+}
+
+impl ScoringStrategy for EstimateAccuracy {
+    fn kind(&self) -> StrategyKind {
+        StrategyKind::EstimateAccuracy
+    }
+
+    fn rules(&self) -> &ScoringRules {
+        &self.rules
+    }
+
+    fn day_boundary(&self) -> &DayBoundary {
+        &self.days
+    }
+
+    fn calculate_task_points(
+        &self,
+        task: &Task,
+        user_stats: &UserStats,
+        avg_time_for_difficulty: Option<f64>,
+    ) -> PointsBreakdown {
+        let base_points = task.difficulty * self.rules.base_points_multiplier;
+        let streak_multiplier = calculate_streak_multiplier(user_stats, &self.rules);
+
+        // Up to 50% of base points for landing exactly on the expectation
+        let accuracy_bonus = estimate_accuracy(task, expected_seconds(task, avg_time_for_difficulty))
+            .map(|accuracy| base_points as f32 * 0.5 * accuracy)
+            .unwrap_or(0.0);
+
+        let on_time_bonus = calculate_on_time_bonus(task, &self.rules, &self.days);
+
+        let raw_total = (base_points as f32 * streak_multiplier * user_stats.streak_bonus_pool
+            + accuracy_bonus
+            + on_time_bonus) as i32;
+        let total_points = raw_total.max(base_points / 2);
+        let accuracy_points = accuracy_bonus as i32;
+        let on_time_points = on_time_bonus as i32;
+
+        PointsBreakdown {
+            base: base_points,
+            streak_multiplier,
+            bonus_pool: user_stats.streak_bonus_pool,
+            streak_bonus: raw_total - base_points - accuracy_points - on_time_points,
+            accuracy_bonus: accuracy_points,
+            on_time_bonus: on_time_points,
+            floor_adjustment: total_points - raw_total,
+            total: total_points,
+            ..PointsBreakdown::flat()
+        }
+    }
+
+    /// Average closeness to the expected time, as a 0-100 score
+    fn efficiency_score(&self, tasks: &[Task], avg_times: &[(i32, f64)]) -> f32 {
+        let accuracies: Vec<f32> = tasks
+            .iter()
+            .filter_map(|task| {
+                let avg = avg_times
+                    .iter()
+                    .find(|(diff, _)| *diff == task.difficulty)
+                    .map(|(_, avg)| *avg);
+                estimate_accuracy(task, expected_seconds(task, avg))
+            })
+            .collect();
+
+        if accuracies.is_empty() {
+            0.0
+        } else {
+            accuracies.iter().sum::<f32>() / accuracies.len() as f32 * 100.0
+        }
+    }
+}
+
+/// The task's own estimate when it has one, otherwise the usual time for its
+/// difficulty
+fn expected_seconds(task: &Task, avg_time_for_difficulty: Option<f64>) -> Option<f64> {
+    task.estimate_minutes
+        .map(|minutes| minutes.saturating_mul(60) as f64)
+        .or(avg_time_for_difficulty)
+}
+
+/// 1.0 when the active time matched the expectation exactly, falling to 0.0
+/// at 100% over or under
+fn estimate_accuracy(task: &Task, expected_seconds: Option<f64>) -> Option<f32> {
+    let actual = task.completed_active_seconds()? as f64;
+    let expected = expected_seconds.filter(|e| *e > 0.0)?;
+    let error = ((actual - expected).abs() / expected).min(1.0);
+    Some((1.0 - error) as f32)
+}
 
 /// Calculate points for a completed task based on multiple factors
-pub fn calculate_task_points(
+fn calculate_classic_points(
     task: &Task,
     user_stats: &UserStats,
     avg_time_for_difficulty: Option<f64>,
-) -> i32 {
+    rules: &ScoringRules,
+    days: &DayBoundary,
+) -> PointsBreakdown {
     // Base points from difficulty
-    let base_points = task.difficulty * BASE_POINTS_MULTIPLIER;
+    let base_points = task.difficulty * rules.base_points_multiplier;
 
     // Time efficiency bonus
-    let time_bonus = calculate_time_bonus(task, avg_time_for_difficulty);
+    let time_bonus = calculate_time_bonus(task, avg_time_for_difficulty, rules);
 
     // Streak multiplier (with gradual decay)
-    let streak_multiplier = calculate_streak_multiplier(user_stats);
+    let streak_multiplier = calculate_streak_multiplier(user_stats, rules);
 
     // Focus bonus (fewer pauses = higher bonus)
-    let focus_bonus = calculate_focus_bonus(task);
+    let focus_bonus = calculate_focus_bonus(task, rules);
+
+    // Optional reward for beating the due date
+    let on_time_bonus = calculate_on_time_bonus(task, rules, days);
 
     // Apply multipliers and bonuses
-    let total_points = ((base_points as f32 * streak_multiplier) + time_bonus + focus_bonus) as i32;
+    let raw_total = ((base_points as f32 * streak_multiplier * user_stats.streak_bonus_pool)
+        + time_bonus
+        + focus_bonus
+        + on_time_bonus) as i32;
+    let total_points = raw_total.max(base_points / 2); // Minimum 50% of base points
+
+    // Whole-point components that add up exactly to the total; the streak
+    // share absorbs rounding and goes negative while the bonus pool is decayed
+    let time_points = time_bonus as i32;
+    let focus_points = focus_bonus as i32;
+    let on_time_points = on_time_bonus as i32;
+
+    PointsBreakdown {
+        base: base_points,
+        time_bonus: time_points,
+        streak_multiplier,
+        bonus_pool: user_stats.streak_bonus_pool,
+        streak_bonus: raw_total - base_points - time_points - focus_points - on_time_points,
+        focus_bonus: focus_points,
+        on_time_bonus: on_time_points,
+        floor_adjustment: total_points - raw_total,
+        total: total_points,
+        ..PointsBreakdown::flat()
+    }
+}
 
-    total_points.max(base_points / 2) // Minimum 50% of base points
+pub fn sum_points(grants: &[PointGrant]) -> i32 {
+    grants.iter().map(|g| g.points).sum()
 }
 
 /// Calculate bonus for completing task quickly
-fn calculate_time_bonus(task: &Task, avg_time: Option<f64>) -> f32 {
-    if let (Some(started), Some(completed)) = (task.started_at, task.completed_at) {
-        let actual_time = (completed - started).num_seconds() as f64 - task.total_pause_duration as f64;
+fn calculate_time_bonus(task: &Task, avg_time: Option<f64>, rules: &ScoringRules) -> f32 {
+    if let Some(active_time) = task.completed_active_seconds() {
+        let actual_time = active_time as f64;
 
         if let Some(avg) = avg_time {
             if actual_time < avg {
                 // Faster than average: bonus up to 50% of base points
                 let speed_ratio = ((avg - actual_time) / avg) as f32;
-                return (task.difficulty * BASE_POINTS_MULTIPLIER) as f32 * speed_ratio.min(0.5);
+                return (task.difficulty * rules.base_points_multiplier) as f32 * speed_ratio.min(0.5);
             }
         } else {
             // First task of this difficulty: small completion bonus
@@ -48,56 +274,71 @@ fn calculate_time_bonus(task: &Task, avg_time: Option<f64>) -> f32 {
     0.0
 }
 
-/// Calculate streak multiplier with forgiveness mechanism
-fn calculate_streak_multiplier(stats: &UserStats) -> f32 {
-    // Base multiplier from streak
-    let streak_mult = 1.0 + (stats.current_streak as f32 * 0.05).min(0.5); // Max 50% bonus
+/// Share of base points for finishing on or before the due date, counted
+/// in the user's days; nothing when the rule is off or there is no due date
+fn calculate_on_time_bonus(task: &Task, rules: &ScoringRules, days: &DayBoundary) -> f32 {
+    match (task.due_date, task.completed_at) {
+        (Some(due), Some(completed)) if days.day_of(completed) <= due => {
+            (task.difficulty * rules.base_points_multiplier) as f32 * rules.on_time_bonus
+        }
+        _ => 0.0,
+    }
+}
 
-    // Apply bonus pool (decays gradually instead of instant reset)
-    streak_mult * stats.streak_bonus_pool
+/// Calculate streak multiplier; the bonus pool (which decays gradually
+/// instead of resetting instantly) is applied on top by the caller
+fn calculate_streak_multiplier(stats: &UserStats, rules: &ScoringRules) -> f32 {
+    1.0 + (stats.current_streak as f32 * rules.streak_step).min(rules.streak_cap)
 }
 
 /// Calculate focus bonus based on task interruptions (pauses)
-fn calculate_focus_bonus(task: &Task) -> f32 {
-    if let (Some(started), Some(completed)) = (task.started_at, task.completed_at) {
-        let total_time = (completed - started).num_seconds();
-        let active_time = total_time - task.total_pause_duration;
-
+fn calculate_focus_bonus(task: &Task, rules: &ScoringRules) -> f32 {
+    if let (Some(total_time), Some(active_time)) = (task.elapsed_seconds(), task.completed_active_seconds()) {
         if total_time > 0 {
             let focus_ratio = active_time as f32 / total_time as f32;
             // Higher focus ratio = more bonus (up to 20% of base points)
-            return (task.difficulty * BASE_POINTS_MULTIPLIER) as f32 * (focus_ratio - 0.8).max(0.0) * 0.5;
+            return (task.difficulty * rules.base_points_multiplier) as f32 * (focus_ratio - 0.8).max(0.0) * 0.5;
         }
     }
     0.0
 }
 
-/// Update user stats after completing a task
-pub fn update_stats_on_completion(stats: &mut UserStats, task: &Task, points: i32) {
+/// Count a completion towards the running totals and the streak
+fn record_completion(stats: &mut UserStats, task: &Task, points: i32, rules: &ScoringRules, days: &DayBoundary) {
     stats.total_points += points;
     stats.tasks_completed += 1;
 
     // Update focus time
-    if let (Some(started), Some(completed)) = (task.started_at, task.completed_at) {
-        let active_time = (completed - started).num_seconds() - task.total_pause_duration;
+    if let Some(active_time) = task.completed_active_seconds() {
         stats.total_focus_time += active_time;
     }
 
-    // Update streaks
-    update_streak(stats);
+    // Update streaks, measured from the task's own completion time so that
+    // replaying history gives the same result as living through it
+    if let Some(completed_at) = task.completed_at {
+        update_streak(stats, completed_at, rules, days);
+    }
+}
 
-    // Check for milestone bonuses
-    if MILESTONE_THRESHOLDS.contains(&stats.tasks_completed) {
-        stats.total_points += MILESTONE_BONUS * (stats.tasks_completed / 10).max(1);
+/// Check for milestone bonuses
+fn award_milestones(stats: &mut UserStats, rules: &ScoringRules) -> Vec<PointGrant> {
+    let mut grants = Vec::new();
+    if rules.milestone_thresholds.contains(&stats.tasks_completed) {
+        let bonus = rules.milestone_bonus * (stats.tasks_completed / 10).max(1);
+        stats.total_points += bonus;
+        grants.push(PointGrant {
+            reason: PointReason::Milestone,
+            points: bonus,
+        });
     }
+    grants
 }
 
 /// Update streak with forgiveness mechanism
-fn update_streak(stats: &mut UserStats) {
-    let now = Utc::now();
-
+fn update_streak(stats: &mut UserStats, completed_at: DateTime<Utc>, rules: &ScoringRules, days: &DayBoundary) {
     if let Some(last_completion) = stats.last_completion_date {
-        let days_since = (now - last_completion).num_days();
+        // Calendar days in the user's timezone, not 24-hour periods
+        let days_since = days.days_between(last_completion, completed_at);
 
         match days_since {
             0 => {
@@ -114,25 +355,26 @@ fn update_streak(stats: &mut UserStats) {
             }
             2..=3 => {
                 // 2-3 days gap: Gradual decay instead of reset
-                stats.streak_bonus_pool *= 0.7;
+                stats.streak_bonus_pool *= rules.short_gap_decay;
                 stats.current_streak = (stats.current_streak as f32 * 0.5) as i32;
             }
             _ => {
                 // More than 3 days: Reset streak but keep some bonus pool
-                stats.streak_bonus_pool *= 0.3;
+                stats.streak_bonus_pool *= rules.long_gap_decay;
                 stats.current_streak = 0;
             }
         }
     } else {
         // First task ever
         stats.current_streak = 1;
+        stats.longest_streak = stats.longest_streak.max(1);
     }
 
-    stats.last_completion_date = Some(now);
+    stats.last_completion_date = Some(completed_at);
 }
 
 /// Calculate focus score (0-100) based on recent task completion patterns
-pub fn calculate_focus_score(tasks: &[Task]) -> f32 {
+fn calculate_focus_score(tasks: &[Task]) -> f32 {
     if tasks.is_empty() {
         return 0.0;
     }
@@ -140,10 +382,8 @@ pub fn calculate_focus_score(tasks: &[Task]) -> f32 {
     let total_focus_ratio: f32 = tasks
         .iter()
         .filter_map(|task| {
-            if let (Some(started), Some(completed)) = (task.started_at, task.completed_at) {
-                let total_time = (completed - started).num_seconds();
+            if let (Some(total_time), Some(active_time)) = (task.elapsed_seconds(), task.completed_active_seconds()) {
                 if total_time > 0 {
-                    let active_time = total_time - task.total_pause_duration;
                     return Some(active_time as f32 / total_time as f32);
                 }
             }
@@ -151,7 +391,7 @@ pub fn calculate_focus_score(tasks: &[Task]) -> f32 {
         })
         .sum();
 
-    let completed_count = tasks.iter().filter(|t| matches!(t.status, crate::models::TaskStatus::Completed)).count();
+    let completed_count = tasks.iter().filter(|t| matches!(t.status, TaskStatus::Completed)).count();
 
     if completed_count > 0 {
         (total_focus_ratio / completed_count as f32 * 100.0).min(100.0)
@@ -161,7 +401,7 @@ pub fn calculate_focus_score(tasks: &[Task]) -> f32 {
 }
 
 /// Calculate efficiency score (0-100) based on time vs. expected time
-pub fn calculate_efficiency_score(
+fn calculate_efficiency_score(
     tasks: &[Task],
     avg_times: &[(i32, f64)], // (difficulty, avg_time)
 ) -> f32 {
@@ -172,8 +412,8 @@ pub fn calculate_efficiency_score(
     let efficiency_sum: f32 = tasks
         .iter()
         .filter_map(|task| {
-            if let (Some(started), Some(completed)) = (task.started_at, task.completed_at) {
-                let actual_time = (completed - started).num_seconds() as f64 - task.total_pause_duration as f64;
+            if let Some(active_time) = task.completed_active_seconds() {
+                let actual_time = active_time as f64;
 
                 if let Some(&(_, avg_time)) = avg_times.iter().find(|(diff, _)| *diff == task.difficulty) {
                     if actual_time > 0.0 {
@@ -187,7 +427,7 @@ pub fn calculate_efficiency_score(
         })
         .sum();
 
-    let completed_count = tasks.iter().filter(|t| matches!(t.status, crate::models::TaskStatus::Completed)).count();
+    let completed_count = tasks.iter().filter(|t| matches!(t.status, TaskStatus::Completed)).count();
 
     if completed_count > 0 {
         ((efficiency_sum / completed_count as f32) * 50.0).min(100.0)
@@ -195,3 +435,37 @@ pub fn calculate_efficiency_score(
         0.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, FakeClock};
+    use chrono::{Duration, NaiveTime, TimeZone};
+    use chrono_tz::Tz;
+
+    #[test]
+    fn streak_follows_calendar_days() {
+        let clock = FakeClock::new(Utc.with_ymd_and_hms(2026, 3, 2, 23, 0, 0).unwrap());
+        let rules = ScoringRules::default();
+        let days = DayBoundary::new(Some(Tz::UTC), NaiveTime::MIN);
+        let mut stats = UserStats::default();
+
+        update_streak(&mut stats, clock.now(), &rules, &days);
+        assert_eq!(stats.current_streak, 1);
+
+        // Two hours later is already the next day
+        clock.advance(Duration::hours(2));
+        update_streak(&mut stats, clock.now(), &rules, &days);
+        assert_eq!(stats.current_streak, 2);
+
+        clock.advance(Duration::hours(20));
+        update_streak(&mut stats, clock.now(), &rules, &days);
+        assert_eq!(stats.current_streak, 2);
+
+        clock.advance(Duration::days(5));
+        update_streak(&mut stats, clock.now(), &rules, &days);
+        assert_eq!(stats.current_streak, 0);
+        assert_eq!(stats.longest_streak, 2);
+        assert_eq!(stats.last_completion_date, Some(clock.now()));
+    }
+}
@@ -0,0 +1,126 @@
+use crate::clock::Clock;
+use crate::config::Config;
+use crate::db::Database;
+use crate::models::{Task, UserStats};
+use anyhow::Result;
+use clap::Args;
+use serde::Serialize;
+
+pub const DEFAULT_FORMAT: &str = "▶ {title} {time} | streak {streak} | +{today} today";
+pub const DEFAULT_IDLE_FORMAT: &str = "streak {streak} | +{today} today";
+
+/// Options for the one-line status segment
+#[derive(Debug, Args)]
+pub struct StatusArgs {
+    /// Output template. Placeholders: {title} {time} {difficulty} {streak} {today} {points}
+    #[arg(short, long, default_value = DEFAULT_FORMAT)]
+    pub format: String,
+    /// Template used when no task is in progress
+    #[arg(long, default_value = DEFAULT_IDLE_FORMAT)]
+    pub idle: String,
+    /// Truncate task titles longer than this many characters
+    #[arg(long, default_value_t = 30)]
+    pub max_title: usize,
+}
+
+#[derive(Serialize)]
+struct StatusReport {
+    task: Option<Task>,
+    active_seconds: Option<i64>,
+    streak: i32,
+    today_points: i32,
+    total_points: i32,
+}
+
+/// Print the running task, its live timer, the streak and today's points.
+///
+/// Meant to be polled by tmux or zellij every few seconds, so it opens the
+/// database read-only, skips migrations and never writes.
+pub fn run(args: &StatusArgs, clock: &dyn Clock, json: bool) -> Result<()> {
+    let config = Config::load()?;
+    let boundary = config.day.boundary()?;
+    let now = clock.now();
+
+    // Before the first run there is nothing to report but an idle line
+    let (task, stats, today_points) = match Database::open_read_only()? {
+        Some(db) => {
+            let today_points = db
+                .get_daily_stats(1, now, &boundary, None)?
+                .first()
+                .map(|day| day.points_earned)
+                .unwrap_or(0);
+            (db.get_active_task()?, db.get_user_stats()?, today_points)
+        }
+        None => (None, UserStats::default(), 0),
+    };
+    let active_seconds = task.as_ref().map(|t| t.active_seconds_at(now));
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string(&StatusReport {
+                task,
+                active_seconds,
+                streak: stats.current_streak,
+                today_points,
+                total_points: stats.total_points,
+            })?
+        );
+        return Ok(());
+    }
+
+    let template = if task.is_some() { &args.format } else { &args.idle };
+    let line = fill_template(template, |name| match name {
+        "title" => Some(task.as_ref().map(|t| truncate(&t.title, args.max_title)).unwrap_or_default()),
+        "time" => Some(active_seconds.map(format_timer).unwrap_or_default()),
+        "difficulty" => Some(task.as_ref().map(|t| t.difficulty.to_string()).unwrap_or_default()),
+        "streak" => Some(stats.current_streak.to_string()),
+        "today" => Some(today_points.to_string()),
+        "points" => Some(stats.total_points.to_string()),
+        _ => None,
+    });
+
+    println!("{}", line);
+    Ok(())
+}
+
+/// Expand `{name}` placeholders in a single pass over the template, so braces
+/// in the substituted values (a task titled `fix {streak} bug`) are left alone.
+/// Unknown placeholders are printed as written.
+fn fill_template(template: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    let mut line = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        line.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let placeholder = rest
+            .find('}')
+            .and_then(|close| value(&rest[1..close]).map(|text| (close, text)));
+        match placeholder {
+            Some((close, text)) => {
+                line.push_str(&text);
+                rest = &rest[close + 1..];
+            }
+            None => {
+                line.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    line.push_str(rest);
+    line
+}
+
+/// Compact H:MM:SS for narrow status bars
+fn format_timer(seconds: i64) -> String {
+    format!("{}:{:02}:{:02}", seconds / 3600, (seconds % 3600) / 60, seconds % 60)
+}
+
+fn truncate(title: &str, max: usize) -> String {
+    if title.chars().count() <= max {
+        return title.to_string();
+    }
+    let mut short: String = title.chars().take(max.saturating_sub(1)).collect();
+    short.push('…');
+    short
+}
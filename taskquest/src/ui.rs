@@ -1,12 +1,13 @@
 use crate::app::{App, InputFocus, InputMode, View};
-use crate::models::TaskStatus;
-use crate::scoring;
+use crate::config::{Config, StrategyKind};
+use crate::models::{self, SubtaskProgress, Task, TaskEvent, TaskEventKind, TaskStatus};
+use chrono::{DateTime, NaiveDate, Utc};
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{
-        Axis, BarChart, Block, Borders, Chart, Dataset, Gauge, GraphType, List, ListItem,
+        Axis, BarChart, Block, Borders, Chart, Clear, Dataset, Gauge, GraphType, List, ListItem, ListState,
         Paragraph, Wrap,
     },
     Frame,
@@ -29,6 +30,58 @@ pub fn render(f: &mut Frame, app: &mut App) {
     render_header(f, chunks[0], app);
     render_content(f, chunks[1], app);
     render_footer(f, chunks[2], app);
+
+    if app.input_mode == InputMode::ConfirmRecompute {
+        render_recompute_prompt(f, f.area(), app);
+    }
+}
+
+fn render_recompute_prompt(f: &mut Frame, area: Rect, app: &App) {
+    let popup_area = centered_rect(70, 60, area);
+
+    let (title, mut text) = match &app.previous_strategy {
+        Some(previous) => (
+            " Scoring Strategy Changed ",
+            vec![Line::from(vec![
+                Span::raw("Scoring strategy changed from "),
+                Span::styled(previous.clone(), Style::default().fg(Color::Yellow)),
+                Span::raw(" to "),
+                Span::styled(
+                    app.strategy.kind().as_str().to_string(),
+                    Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(". Recompute history?"),
+            ])],
+        ),
+        None => (
+            " Recompute Points ",
+            vec![Line::from(format!(
+                "Replaying completed tasks with the '{}' strategy would change:",
+                app.strategy.kind().as_str()
+            ))],
+        ),
+    };
+
+    text.push(Line::from(""));
+    if let Some(plan) = &app.pending_recompute {
+        text.extend(plan.diff_lines().into_iter().map(Line::from));
+    }
+    text.push(Line::from(""));
+    text.push(Line::from(Span::styled(
+        "y: Apply recompute | n: Keep existing points",
+        Style::default().fg(Color::Gray),
+    )));
+
+    f.render_widget(Clear, popup_area);
+    let prompt = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .style(Style::default().fg(Color::Cyan)),
+        )
+        .wrap(Wrap { trim: false });
+    f.render_widget(prompt, popup_area);
 }
 
 fn render_header(f: &mut Frame, area: Rect, app: &App) {
@@ -83,6 +136,10 @@ fn render_header(f: &mut Frame, area: Rect, app: &App) {
         " Points: {} | Streak: {}🔥 | Completed: {} ",
         app.user_stats.total_points, app.user_stats.current_streak, app.user_stats.tasks_completed
     );
+    let stats_text = match &app.current_project {
+        Some(project) => format!(" Project: {} |{}", project.name, stats_text),
+        None => stats_text,
+    };
     let stats_widget = Paragraph::new(stats_text)
         .block(Block::default().borders(Borders::ALL))
         .style(Style::default().fg(Color::Green))
@@ -94,35 +151,32 @@ fn render_content(f: &mut Frame, area: Rect, app: &mut App) {
     match app.current_view {
         View::TaskList => render_task_list(f, area, app),
         View::Stats => render_stats(f, area, app),
-        View::Help => render_help(f, area),
+        View::Help => render_help(f, area, app),
+    }
+
+    match app.input_mode {
+        InputMode::PickingProject => render_project_picker(f, f.area(), app),
+        InputMode::NamingProject => render_project_name_input(f, f.area(), app),
+        InputMode::Searching => render_search(f, f.area(), app),
+        _ => {}
     }
 }
 
 fn render_task_list(f: &mut Frame, area: Rect, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(5), Constraint::Length(10)])
+        .constraints([Constraint::Min(5), Constraint::Length(14)])
         .split(area);
 
     // Task list
-    let tasks = app.visible_tasks();
-    let items: Vec<ListItem> = tasks
+    let today = app.today();
+    let rows = app.visible_rows();
+    let items: Vec<ListItem> = rows
         .iter()
         .enumerate()
-        .map(|(i, task)| {
-            let status_symbol = match task.status {
-                TaskStatus::Pending => "○",
-                TaskStatus::InProgress => "▶",
-                TaskStatus::Paused => "❚❚",
-                TaskStatus::Completed => "✓",
-            };
-
-            let status_color = match task.status {
-                TaskStatus::Pending => Color::Gray,
-                TaskStatus::InProgress => Color::Green,
-                TaskStatus::Paused => Color::Yellow,
-                TaskStatus::Completed => Color::Blue,
-            };
+        .map(|(i, row)| {
+            let task = row.task;
+            let (status_symbol, status_color) = status_symbol(task);
 
             let difficulty_bar = "▰".repeat(task.difficulty as usize);
             let difficulty_color = match task.difficulty {
@@ -132,12 +186,34 @@ fn render_task_list(f: &mut Frame, area: Rect, app: &mut App) {
                 _ => Color::Red,
             };
 
-            let content = vec![Line::from(vec![
+            let progress = app.subtask_progress.get(&task.id);
+            let fold_marker = match (progress, row.has_children) {
+                // A fuzzy filter opens collapsed parents
+                (Some(_), true) if app.collapsed.contains(&task.id) && app.fuzzy_filter.is_empty() => "▸ ",
+                (Some(_), true) => "▾ ",
+                _ => "  ",
+            };
+
+            let mut content = vec![Line::from(vec![
+                Span::raw("  ".repeat(row.depth)),
+                Span::styled(fold_marker, Style::default().fg(Color::DarkGray)),
                 Span::styled(
                     format!("{} ", status_symbol),
                     Style::default().fg(status_color).add_modifier(Modifier::BOLD),
                 ),
-                Span::styled(&task.title, Style::default().fg(Color::White)),
+            ])];
+            let title_style = if task.is_overdue(today) {
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            match models::fuzzy_match(&app.fuzzy_filter, &task.title) {
+                Some(found) if !found.positions.is_empty() => {
+                    content[0].spans.extend(fuzzy_spans(&task.title, &found.positions, title_style));
+                }
+                _ => content[0].spans.push(Span::styled(task.title.clone(), title_style)),
+            }
+            content[0].spans.extend([
                 Span::raw(" "),
                 Span::styled(
                     format!("[{}]", difficulty_bar),
@@ -147,7 +223,33 @@ fn render_task_list(f: &mut Frame, area: Rect, app: &mut App) {
                     format!(" ({})", task.difficulty),
                     Style::default().fg(Color::DarkGray),
                 ),
-            ])];
+            ]);
+            if let (Some(due), false) = (task.due_date, task.status == TaskStatus::Completed) {
+                content[0].spans.push(Span::styled(
+                    format!(" {}", models::describe_due(due, today)),
+                    Style::default().fg(due_color(task, today)),
+                ));
+            }
+            if task.recurrence.is_some() && task.status != TaskStatus::Completed {
+                content[0].spans.push(Span::styled(" ↻", Style::default().fg(Color::Magenta)));
+            }
+            if let Some(progress) = progress {
+                let color = if progress.is_finished() { Color::Green } else { Color::Yellow };
+                content[0].spans.push(Span::styled(
+                    format!(" {}/{}", progress.done, progress.total),
+                    Style::default().fg(color),
+                ));
+            }
+            if let (None, Some(project)) = (&app.current_project, &task.project) {
+                content[0].spans.push(Span::styled(
+                    format!(" @{}", project),
+                    Style::default().fg(Color::LightBlue),
+                ));
+            }
+            for tag in &task.tags {
+                content[0].spans.push(Span::raw(" "));
+                content[0].spans.push(tag_chip(tag));
+            }
 
             let style = if i == app.selected_task_index {
                 Style::default()
@@ -161,11 +263,24 @@ fn render_task_list(f: &mut Frame, area: Rect, app: &mut App) {
         })
         .collect();
 
-    let title = if app.show_completed {
-        " All Tasks (Press 'c' to hide completed) "
+    let mut title = if app.show_completed {
+        " All Tasks (Press 'c' to hide completed) ".to_string()
     } else {
-        " Active Tasks (Press 'c' to show completed) "
+        " Active Tasks (Press 'c' to show completed) ".to_string()
     };
+    if !app.tag_filter.is_empty() {
+        let tags: Vec<String> = app.tag_filter.iter().map(|t| format!("#{}", t)).collect();
+        title = format!("{}[filter: {}] ", title, tags.join(" "));
+    }
+    if let Some(project) = &app.current_project {
+        title = format!("{}[project: {}] ", title, project.name);
+    }
+    if app.input_mode == InputMode::FuzzyFiltering {
+        title = format!("{}[find: {}█] ", title, app.fuzzy_filter);
+    } else if !app.fuzzy_filter.is_empty() {
+        title = format!("{}[find: {}] (Esc: show all) ", title, app.fuzzy_filter);
+    }
+    title = format!("{}[sort: {}] ", title, app.sort_mode.label());
 
     let list = List::new(items)
         .block(Block::default().borders(Borders::ALL).title(title))
@@ -175,7 +290,9 @@ fn render_task_list(f: &mut Frame, area: Rect, app: &mut App) {
 
     // Task details
     if let Some(task) = app.selected_task() {
-        render_task_details(f, chunks[1], task);
+        let events = app.db.get_task_events(task.id).unwrap_or_default();
+        let subtasks = app.subtask_progress.get(&task.id).copied();
+        render_task_details(f, chunks[1], task, subtasks, &events, app.clock.now(), today);
     } else {
         let empty = Paragraph::new("No tasks yet! Press 'a' to add one.")
             .block(Block::default().borders(Borders::ALL).title(" Details "))
@@ -185,15 +302,318 @@ fn render_task_list(f: &mut Frame, area: Rect, app: &mut App) {
     }
 
     // Input overlay
-    if app.input_mode != InputMode::Normal {
+    if matches!(app.input_mode, InputMode::AddingTask | InputMode::EditingTask) {
         render_input_popup(f, f.area(), app);
     }
+    if app.input_mode == InputMode::FilteringTags {
+        render_tag_filter(f, f.area(), app);
+    }
+    if app.input_mode == InputMode::EditingNotes {
+        render_notes_editor(f, f.area(), app);
+    }
+}
+
+fn render_notes_editor(f: &mut Frame, area: Rect, app: &mut App) {
+    let popup_area = centered_rect(70, 70, area);
+    let title = app
+        .editing_task_id
+        .and_then(|id| app.tasks.iter().find(|t| t.id == id))
+        .map_or_else(|| " Notes ".to_string(), |t| format!(" Notes: {} ", t.title));
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(Style::default().fg(Color::Green).add_modifier(Modifier::BOLD));
+    let inner = block.inner(popup_area);
+
+    // No soft wrapping: long lines scroll sideways so the cursor maps to
+    // one screen cell per character
+    let (width, height) = (inner.width as usize, inner.height as usize);
+    let (scroll_row, scroll_col) = app.notes_editor.scroll_to_cursor(width, height);
+    let lines: Vec<Line> = app
+        .notes_editor
+        .lines()
+        .iter()
+        .skip(scroll_row)
+        .take(height)
+        .map(|line| Line::from(line.chars().skip(scroll_col).take(width).collect::<String>()))
+        .collect();
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(
+        Paragraph::new(lines).style(Style::default().fg(Color::White)).block(block),
+        popup_area,
+    );
+
+    let (row, col) = app.notes_editor.cursor();
+    f.set_cursor_position((
+        inner.x + (col - scroll_col) as u16,
+        inner.y + (row - scroll_row) as u16,
+    ));
+}
+
+/// Red once overdue, yellow on the day it is due, gray otherwise
+fn due_color(task: &Task, today: NaiveDate) -> Color {
+    match task.days_until_due(today) {
+        _ if task.status == TaskStatus::Completed => Color::DarkGray,
+        Some(d) if d < 0 => Color::Red,
+        Some(0) => Color::Yellow,
+        _ => Color::DarkGray,
+    }
+}
+
+fn render_project_picker(f: &mut Frame, area: Rect, app: &App) {
+    let popup_area = centered_rect(50, 50, area);
+
+    let all_stats = app.db.get_project_stats(None).unwrap_or_default();
+    let mut rows = vec![(
+        "All projects".to_string(),
+        Some(all_stats),
+    )];
+    for project in &app.projects {
+        rows.push((project.name.clone(), app.db.get_project_stats(Some(project.id)).ok()));
+    }
+    rows.push(("+ New project".to_string(), None));
+
+    let items: Vec<ListItem> = rows
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, stats))| {
+            let mut spans = vec![Span::raw(format!("{:<24}", name))];
+            if let Some(stats) = stats {
+                spans.push(Span::styled(
+                    format!("{:>6} pts", stats.points_earned),
+                    Style::default().fg(Color::Yellow),
+                ));
+                spans.push(Span::styled(
+                    format!("  {}h {}m", stats.focus_time / 3600, (stats.focus_time % 3600) / 60),
+                    Style::default().fg(Color::Gray),
+                ));
+            }
+            let style = if i == app.project_cursor {
+                Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            ListItem::new(Line::from(spans)).style(style)
+        })
+        .collect();
+
+    f.render_widget(Clear, popup_area);
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Projects ")
+            .style(Style::default().fg(Color::Cyan)),
+    );
+    f.render_widget(list, popup_area);
+}
+
+fn render_project_name_input(f: &mut Frame, area: Rect, app: &App) {
+    let popup_area = centered_rect(40, 20, area);
+    let popup_area = Rect {
+        height: popup_area.height.min(3),
+        ..popup_area
+    };
+
+    f.render_widget(Clear, popup_area);
+    let input = Paragraph::new(format!("{}█", app.input_buffer))
+        .style(Style::default().fg(Color::Yellow))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" New Project Name ")
+                .border_style(Style::default().fg(Color::Green)),
+        );
+    f.render_widget(input, popup_area);
+}
+
+const TAG_COLORS: [Color; 8] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::LightBlue,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::LightRed,
+];
+
+/// Stable colour per tag name, so a tag looks the same everywhere
+fn tag_color(tag: &str) -> Color {
+    let hash = tag.bytes().fold(0usize, |acc, b| acc.wrapping_mul(31).wrapping_add(b as usize));
+    TAG_COLORS[hash % TAG_COLORS.len()]
+}
+
+/// `text` with the characters at `positions` (char indices) highlighted
+fn fuzzy_spans(text: &str, positions: &[usize], base: Style) -> Vec<Span<'static>> {
+    let highlight = base.fg(Color::Yellow).add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (i, c) in text.chars().enumerate() {
+        let matched = positions.contains(&i);
+        if matched != run_matched && !run.is_empty() {
+            spans.push(Span::styled(std::mem::take(&mut run), if run_matched { highlight } else { base }));
+        }
+        run_matched = matched;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        spans.push(Span::styled(run, if run_matched { highlight } else { base }));
+    }
+    spans
+}
+
+/// List icon and colour for a task's status; blocked tasks get their own
+fn status_symbol(task: &Task) -> (&'static str, Color) {
+    match task.status {
+        _ if task.is_blocked() && task.status != TaskStatus::InProgress => ("⊘", Color::Red),
+        TaskStatus::Pending => ("○", Color::Gray),
+        TaskStatus::InProgress => ("▶", Color::Green),
+        TaskStatus::Paused => ("❚❚", Color::Yellow),
+        TaskStatus::Completed => ("✓", Color::Blue),
+    }
+}
+
+fn tag_chip(tag: &str) -> Span<'static> {
+    Span::styled(
+        format!(" #{} ", tag),
+        Style::default().fg(Color::Black).bg(tag_color(tag)),
+    )
 }
 
-fn render_task_details(f: &mut Frame, area: Rect, task: &crate::models::Task) {
+/// Search text with its matched words highlighted
+fn match_spans(marked: &str, base: Style) -> Vec<Span<'static>> {
+    models::match_segments(marked)
+        .into_iter()
+        .map(|(text, is_match)| {
+            let style = if is_match {
+                base.fg(Color::Yellow).add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+            } else {
+                base
+            };
+            Span::styled(text.to_string(), style)
+        })
+        .collect()
+}
+
+fn render_search(f: &mut Frame, area: Rect, app: &App) {
+    let popup_area = centered_rect(70, 70, area);
+    f.render_widget(Clear, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(popup_area);
+
+    let input = Paragraph::new(format!("{}█", app.search_input))
+        .style(Style::default().fg(Color::Yellow))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Search titles and notes ")
+                .border_style(Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+        );
+    f.render_widget(input, chunks[0]);
+
+    let items: Vec<ListItem> = app
+        .search_results
+        .iter()
+        .map(|hit| {
+            let task = &hit.task;
+            let (symbol, color) = status_symbol(task);
+            let date = match task.completed_at {
+                Some(done) => format!("done {}", done.format("%Y-%m-%d")),
+                None => format!("added {}", task.created_at.format("%Y-%m-%d")),
+            };
+
+            let mut title = vec![
+                Span::styled(format!("{} ", symbol), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                Span::styled(format!("#{} ", task.id), Style::default().fg(Color::DarkGray)),
+            ];
+            title.extend(match_spans(&hit.title, Style::default().fg(Color::White)));
+            for tag in &task.tags {
+                title.push(Span::raw(" "));
+                title.push(tag_chip(tag));
+            }
+            if let Some(project) = &task.project {
+                title.push(Span::styled(format!("  {}", project), Style::default().fg(Color::Cyan)));
+            }
+            title.push(Span::styled(format!("  {}", date), Style::default().fg(Color::DarkGray)));
+
+            let mut lines = vec![Line::from(title)];
+            if let Some(notes) = &hit.notes {
+                let mut excerpt = vec![Span::raw("     ")];
+                excerpt.extend(match_spans(&notes.replace('\n', " "), Style::default().fg(Color::Gray)));
+                lines.push(Line::from(excerpt));
+            }
+            ListItem::new(lines)
+        })
+        .collect();
+
+    let title = match (app.search_input.trim().is_empty(), app.search_results.len()) {
+        (true, _) => " Type to search ".to_string(),
+        (false, 0) => " No matches ".to_string(),
+        (false, 1) => " 1 match ".to_string(),
+        (false, n) => format!(" {} matches ", n),
+    };
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .style(Style::default().fg(Color::Cyan)),
+        )
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD));
+    let mut state = ListState::default().with_selected(Some(app.search_cursor));
+    f.render_stateful_widget(list, chunks[1], &mut state);
+}
+
+fn render_tag_filter(f: &mut Frame, area: Rect, app: &App) {
+    let popup_area = centered_rect(40, 50, area);
+
+    let items: Vec<ListItem> = app
+        .available_tags
+        .iter()
+        .enumerate()
+        .map(|(i, tag)| {
+            let checked = if app.tag_filter.contains(tag) { "[x]" } else { "[ ]" };
+            let line = Line::from(vec![Span::raw(format!("{} ", checked)), tag_chip(tag)]);
+            let style = if i == app.tag_cursor {
+                Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            ListItem::new(line).style(style)
+        })
+        .collect();
+
+    f.render_widget(Clear, popup_area);
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Filter by Tag ")
+            .style(Style::default().fg(Color::Cyan)),
+    );
+    f.render_widget(list, popup_area);
+}
+
+fn render_task_details(
+    f: &mut Frame,
+    area: Rect,
+    task: &Task,
+    subtasks: Option<SubtaskProgress>,
+    events: &[TaskEvent],
+    now: DateTime<Utc>,
+    today: NaiveDate,
+) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .constraints([
+            Constraint::Percentage(45),
+            Constraint::Percentage(35),
+            Constraint::Percentage(20),
+        ])
         .split(area);
 
     // Left: Task info
@@ -212,6 +632,87 @@ fn render_task_details(f: &mut Frame, area: Rect, task: &crate::models::Task) {
         ]),
     ];
 
+    if let Some(due) = task.due_date {
+        info_lines[2].spans.push(Span::styled("  Due: ", Style::default().fg(Color::Cyan)));
+        info_lines[2].spans.push(Span::styled(
+            format!("{} ({})", due.format("%a %Y-%m-%d"), models::describe_due(due, today)),
+            Style::default().fg(due_color(task, today)),
+        ));
+    }
+
+    // Project and tags share a line to leave room for timing details
+    let mut grouping = Vec::new();
+    if let Some(project) = &task.project {
+        grouping.push(Span::styled("Project: ", Style::default().fg(Color::Cyan)));
+        grouping.push(Span::raw(format!("{}  ", project)));
+    }
+    if !task.tags.is_empty() {
+        grouping.push(Span::styled("Tags: ", Style::default().fg(Color::Cyan)));
+        for tag in &task.tags {
+            grouping.push(tag_chip(tag));
+            grouping.push(Span::raw(" "));
+        }
+    }
+    if !grouping.is_empty() {
+        info_lines.push(Line::from(grouping));
+    }
+
+    if let Some(estimate) = task.estimate_minutes {
+        let actual = task.active_seconds_at(task.completed_at.unwrap_or(now));
+        let mut spans = vec![
+            Span::styled("Estimate: ", Style::default().fg(Color::Cyan)),
+            Span::raw(models::format_minutes(estimate)),
+        ];
+        if actual > 0 {
+            let error = actual as f64 / (estimate * 60) as f64 - 1.0;
+            let color = match error.abs() {
+                e if e <= 0.2 => Color::Green,
+                e if e <= 0.5 => Color::Yellow,
+                _ => Color::Red,
+            };
+            spans.push(Span::styled("  Actual: ", Style::default().fg(Color::Cyan)));
+            spans.push(Span::raw(format!("{}h {}m ", actual / 3600, (actual % 3600) / 60)));
+            spans.push(Span::styled(format!("({:+.0}%)", error * 100.0), Style::default().fg(color)));
+        }
+        info_lines.push(Line::from(spans));
+    }
+
+    if let Some(rule) = &task.recurrence {
+        info_lines.push(Line::from(vec![
+            Span::styled("Repeats: ", Style::default().fg(Color::Cyan)),
+            Span::styled(rule.to_string(), Style::default().fg(Color::Magenta)),
+        ]));
+    }
+
+    if !task.depends_on.is_empty() {
+        let mut spans = vec![Span::styled("Depends on: ", Style::default().fg(Color::Cyan))];
+        for id in &task.depends_on {
+            let (mark, color) = if task.blocked_by.contains(id) {
+                ("⊘", Color::Red)
+            } else {
+                ("✓", Color::Green)
+            };
+            spans.push(Span::styled(format!("#{} {}  ", id, mark), Style::default().fg(color)));
+        }
+        info_lines.push(Line::from(spans));
+    }
+
+    if let Some(progress) = subtasks {
+        let filled = (progress.ratio() * 10.0).round() as usize;
+        info_lines.push(Line::from(vec![
+            Span::styled("Subtasks: ", Style::default().fg(Color::Cyan)),
+            Span::styled(
+                format!("{}{}", "█".repeat(filled), "░".repeat(10 - filled)),
+                Style::default().fg(Color::Green),
+            ),
+            Span::raw(format!(" {}/{} done", progress.done, progress.total)),
+            Span::styled(
+                format!("  +{} pts from subtasks", progress.points),
+                Style::default().fg(Color::Yellow),
+            ),
+        ]));
+    }
+
     if let Some(started) = task.started_at {
         info_lines.push(Line::from(vec![
             Span::styled("Started: ", Style::default().fg(Color::Cyan)),
@@ -219,12 +720,7 @@ fn render_task_details(f: &mut Frame, area: Rect, task: &crate::models::Task) {
         ]));
 
         if task.status == TaskStatus::InProgress || task.status == TaskStatus::Paused {
-            let elapsed = if task.status == TaskStatus::Paused {
-                task.paused_at.unwrap_or_else(chrono::Utc::now) - started
-            } else {
-                chrono::Utc::now() - started
-            };
-            let active_seconds = elapsed.num_seconds() - task.total_pause_duration;
+            let active_seconds = task.active_seconds_at(now);
             let hours = active_seconds / 3600;
             let minutes = (active_seconds % 3600) / 60;
             let seconds = active_seconds % 60;
@@ -234,6 +730,18 @@ fn render_task_details(f: &mut Frame, area: Rect, task: &crate::models::Task) {
                 Span::raw(format!("{}h {}m {}s", hours, minutes, seconds)),
             ]));
         }
+
+        if let Some(paused) = task.paused_at() {
+            info_lines.push(Line::from(vec![
+                Span::styled("Paused: ", Style::default().fg(Color::Cyan)),
+                Span::raw(paused.format("%Y-%m-%d %H:%M:%S").to_string()),
+            ]));
+        }
+
+        info_lines.push(Line::from(vec![
+            Span::styled("Sessions: ", Style::default().fg(Color::Cyan)),
+            Span::raw(task.time_entries.len().to_string()),
+        ]));
     }
 
     if task.status == TaskStatus::Completed {
@@ -246,30 +754,147 @@ fn render_task_details(f: &mut Frame, area: Rect, task: &crate::models::Task) {
                     .add_modifier(Modifier::BOLD),
             ),
         ]));
+
+        if let Some(breakdown) = &task.points_breakdown {
+            info_lines.push(Line::from(vec![
+                Span::styled("  ", Style::default()),
+                Span::styled(breakdown.summary(), Style::default().fg(Color::Gray)),
+                Span::styled(
+                    format!(
+                        " (streak ×{:.2}, pool {:.0}%)",
+                        breakdown.streak_multiplier,
+                        breakdown.bonus_pool * 100.0
+                    ),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]));
+        }
     }
 
+    // Notes get their own box below the details so indentation survives
+    let left = if task.notes.is_empty() {
+        vec![chunks[0]]
+    } else {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(4), Constraint::Percentage(45)])
+            .split(chunks[0])
+            .to_vec()
+    };
+
     let info = Paragraph::new(info_lines)
         .block(Block::default().borders(Borders::ALL).title(" Details "))
         .wrap(Wrap { trim: true });
-    f.render_widget(info, chunks[0]);
+    f.render_widget(info, left[0]);
+
+    if let Some(area) = left.get(1) {
+        let notes = Paragraph::new(task.notes.as_str())
+            .block(Block::default().borders(Borders::ALL).title(" Notes "))
+            .style(Style::default().fg(Color::Gray))
+            .wrap(Wrap { trim: false });
+        f.render_widget(notes, *area);
+    }
+
+    // Middle: History timeline (most recent events that fit)
+    render_task_history(f, chunks[1], events);
 
     // Right: Quick actions
-    let actions = vec![
+    let actions = [
         "Space - Start/Pause",
         "x - Complete",
         "e - Edit",
+        "n - Notes",
         "d - Delete",
+        "u - Undo",
+        "Ctrl-r - Redo",
     ];
     let actions_text: Vec<Line> = actions.iter().map(|s| Line::from(*s)).collect();
 
     let actions_widget = Paragraph::new(actions_text)
         .block(Block::default().borders(Borders::ALL).title(" Actions "))
         .style(Style::default().fg(Color::Gray));
-    f.render_widget(actions_widget, chunks[1]);
+    f.render_widget(actions_widget, chunks[2]);
+}
+
+fn render_task_history(f: &mut Frame, area: Rect, events: &[TaskEvent]) {
+    let visible = area.height.saturating_sub(2) as usize;
+    let skip = events.len().saturating_sub(visible);
+
+    let lines: Vec<Line> = events
+        .iter()
+        .skip(skip)
+        .map(|event| {
+            let (symbol, color) = match event.kind {
+                TaskEventKind::Created => ("+", Color::Gray),
+                TaskEventKind::Edited => ("✎", Color::Magenta),
+                TaskEventKind::Started => ("▶", Color::Green),
+                TaskEventKind::Paused => ("❚❚", Color::Yellow),
+                TaskEventKind::Resumed => ("▶", Color::Green),
+                TaskEventKind::Completed => ("✓", Color::Blue),
+                TaskEventKind::Deleted => ("✗", Color::Red),
+                TaskEventKind::Undone => ("↶", Color::Cyan),
+                TaskEventKind::Redone => ("↷", Color::Cyan),
+            };
+
+            let mut spans = vec![
+                Span::styled(
+                    event.occurred_at.format("%m-%d %H:%M ").to_string(),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(format!("{} ", symbol), Style::default().fg(color)),
+                Span::raw(event.kind.as_str()),
+            ];
+            if let Some(detail) = &event.detail {
+                spans.push(Span::styled(
+                    format!(" {}", detail),
+                    Style::default().fg(Color::Gray),
+                ));
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    let title = format!(" History ({}) ", events.len());
+    let history = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(history, area);
+}
+
+/// With an estimate typed: the difficulty that matches it in the user's
+/// history. Otherwise: how long tasks of the typed difficulty usually take.
+fn difficulty_hint(app: &App) -> Option<Line<'static>> {
+    let difficulty = app.difficulty_input.trim().parse::<i32>().ok();
+    if let Ok(Some(estimate)) = models::parse_estimate(&app.estimate_input) {
+        let suggested = app.suggested_difficulty(estimate).ok()??;
+        let estimate = models::format_minutes(estimate);
+        return Some(if Some(suggested) == difficulty {
+            Line::from(Span::styled(
+                format!("✓ Difficulty {} fits {} in your history", suggested, estimate),
+                Style::default().fg(Color::Green),
+            ))
+        } else {
+            Line::from(Span::styled(
+                format!("Your history suggests difficulty {} for {}", suggested, estimate),
+                Style::default().fg(Color::Yellow),
+            ))
+        });
+    }
+
+    let difficulty = difficulty?;
+    let avg_times = app.db.get_avg_time_by_difficulty(None).ok()?;
+    let (_, avg) = avg_times.iter().find(|(d, _)| *d == difficulty)?;
+    Some(Line::from(Span::styled(
+        format!(
+            "Your difficulty {} tasks take {} on average",
+            difficulty,
+            models::format_minutes((avg / 60.0).round() as i64)
+        ),
+        Style::default().fg(Color::Gray),
+    )))
 }
 
 fn render_input_popup(f: &mut Frame, area: Rect, app: &App) {
-    let popup_area = centered_rect(60, 40, area);
+    let popup_area = centered_rect(60, 70, area);
 
     // Background
     let bg = Block::default()
@@ -284,10 +909,15 @@ fn render_input_popup(f: &mut Frame, area: Rect, app: &App) {
             Constraint::Length(3),
             Constraint::Length(3),
             Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(2),
+            Constraint::Length(3),
         ])
         .split(popup_area);
 
     let title = match app.input_mode {
+        InputMode::AddingTask if app.adding_parent_id.is_some() => " Add Subtask ",
         InputMode::AddingTask => " Add New Task ",
         InputMode::EditingTask => " Edit Task ",
         _ => " Input ",
@@ -300,7 +930,7 @@ fn render_input_popup(f: &mut Frame, area: Rect, app: &App) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(if title_active { "► Task Title" } else { "Task Title" })
+                .title(if title_active { "► Task Title (#tag adds a tag)" } else { "Task Title (#tag adds a tag)" })
                 .border_style(if title_active {
                     Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
                 } else {
@@ -323,13 +953,106 @@ fn render_input_popup(f: &mut Frame, area: Rect, app: &App) {
                     Style::default().fg(Color::DarkGray)
                 }),
         );
-    f.render_widget(difficulty_input, chunks[1]);
+    let sizing = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(chunks[1]);
+    f.render_widget(difficulty_input, sizing[0]);
+
+    // Estimate (highlight if active)
+    let estimate_active = app.input_focus == InputFocus::Estimate;
+    let estimate_input = Paragraph::new(format!("{}{}", app.estimate_input.as_str(), if estimate_active { "█" } else { "" }))
+        .style(Style::default().fg(Color::Yellow))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(if estimate_active { "► Estimate (30m, 2h, 1h30m)" } else { "Estimate (30m, 2h, 1h30m)" })
+                .border_style(if estimate_active {
+                    Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                }),
+        );
+    f.render_widget(estimate_input, sizing[1]);
+
+    // Due date (highlight if active)
+    let due_active = app.input_focus == InputFocus::DueDate;
+    let due_input = Paragraph::new(format!("{}{}", app.due_input.as_str(), if due_active { "█" } else { "" }))
+        .style(Style::default().fg(Color::Yellow))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(if due_active {
+                    "► Due (today, tomorrow, fri, +3d, YYYY-MM-DD)"
+                } else {
+                    "Due (today, tomorrow, fri, +3d, YYYY-MM-DD)"
+                })
+                .border_style(if due_active {
+                    Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                }),
+        );
+    f.render_widget(due_input, chunks[3]);
+
+    // Repeat rule (highlight if active)
+    let recurrence_active = app.input_focus == InputFocus::Recurrence;
+    let recurrence_input = Paragraph::new(format!(
+        "{}{}",
+        app.recurrence_input.as_str(),
+        if recurrence_active { "█" } else { "" }
+    ))
+    .style(Style::default().fg(Color::Yellow))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(if recurrence_active {
+                "► Repeat (daily, weekdays, weekly mon,thu, monthly, every 3 days)"
+            } else {
+                "Repeat (daily, weekdays, weekly mon,thu, monthly, every 3 days)"
+            })
+            .border_style(if recurrence_active {
+                Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            }),
+    );
+    f.render_widget(recurrence_input, chunks[4]);
+
+    // Prerequisites (highlight if active)
+    let dependencies_active = app.input_focus == InputFocus::Dependencies;
+    let dependencies_input = Paragraph::new(format!(
+        "{}{}",
+        app.dependencies_input.as_str(),
+        if dependencies_active { "█" } else { "" }
+    ))
+    .style(Style::default().fg(Color::Yellow))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(if dependencies_active {
+                "► Depends on (task ids, e.g. 3, 7)"
+            } else {
+                "Depends on (task ids, e.g. 3, 7)"
+            })
+            .border_style(if dependencies_active {
+                Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            }),
+    );
+    f.render_widget(dependencies_input, chunks[2]);
+
+    // What past completion times say about the difficulty
+    if let Some(hint) = difficulty_hint(app) {
+        f.render_widget(Paragraph::new(hint).alignment(Alignment::Center), chunks[5]);
+    }
 
     // Instructions
     let instructions = Paragraph::new("Tab: Switch field | Enter: Submit | Esc: Cancel")
         .style(Style::default().fg(Color::Gray))
         .alignment(Alignment::Center);
-    f.render_widget(instructions, chunks[2]);
+    f.render_widget(instructions, chunks[6]);
 
     // Title
     let title_block = Block::default()
@@ -345,6 +1068,7 @@ fn render_stats(f: &mut Frame, area: Rect, app: &mut App) {
         .constraints([
             Constraint::Length(8),  // Stats overview
             Constraint::Length(15), // Charts
+            Constraint::Length(12), // Estimation accuracy and difficulty calibration
             Constraint::Min(5),     // Recent tasks
         ])
         .split(area);
@@ -355,25 +1079,269 @@ fn render_stats(f: &mut Frame, area: Rect, app: &mut App) {
     // Charts
     render_charts(f, chunks[1], app);
 
-    // Recent completed tasks
-    render_recent_tasks(f, chunks[2], app);
+    // How far estimates were off, and whether difficulties match the time taken
+    let accuracy = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(62), Constraint::Percentage(38)])
+        .split(chunks[2]);
+    render_estimates(f, accuracy[0], app);
+    render_calibration(f, accuracy[1], app);
+
+    // Recent completed tasks and the per-tag and per-series breakdowns
+    let bottom = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(40),
+            Constraint::Percentage(28),
+            Constraint::Percentage(32),
+        ])
+        .split(chunks[3]);
+    render_recent_tasks(f, bottom[0], app);
+    render_tag_stats(f, bottom[1], app);
+    render_series_stats(f, bottom[2], app);
+}
+
+/// Estimation error of the last estimated tasks, and on average per
+/// difficulty. Positive errors mean the task took longer than estimated.
+fn render_estimates(f: &mut Frame, area: Rect, app: &App) {
+    let samples = app.db.get_estimate_samples(app.project_scope()).unwrap_or_default();
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(area);
+
+    if samples.is_empty() {
+        let empty = Paragraph::new("Give tasks an estimate (e.g. 30m) and track their time to see how close you get")
+            .block(Block::default().borders(Borders::ALL).title(" Estimation Error "))
+            .style(Style::default().fg(Color::DarkGray))
+            .wrap(Wrap { trim: true });
+        f.render_widget(empty, area);
+        return;
+    }
+
+    // Left: error of the most recent estimated tasks, oldest first
+    let recent = &samples[samples.len().saturating_sub(30)..];
+    let points: Vec<(f64, f64)> = recent
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| (i as f64, (sample.error() * 100.0).clamp(-100.0, 200.0)))
+        .collect();
+    let last_x = (points.len().max(2) - 1) as f64;
+    let zero = [(0.0, 0.0), (last_x, 0.0)];
+    let max_error = points.iter().map(|(_, e)| *e).fold(50.0, f64::max).ceil();
+
+    let chart = Chart::new(vec![
+        Dataset::default()
+            .marker(ratatui::symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::DarkGray))
+            .data(&zero),
+        Dataset::default()
+            .name("error %")
+            .marker(ratatui::symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Magenta))
+            .data(&points),
+    ])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" Estimation Error (last {} tasks) ", recent.len())),
+    )
+    .x_axis(Axis::default().bounds([0.0, last_x]))
+    .y_axis(
+        Axis::default()
+            .bounds([-100.0, max_error])
+            .labels(vec![
+                Line::from("-100%"),
+                Line::from("0%"),
+                Line::from(format!("+{:.0}%", max_error)),
+            ]),
+    );
+    f.render_widget(chart, chunks[0]);
+
+    // Right: average error per difficulty
+    let items: Vec<ListItem> = models::estimate_accuracy_by_difficulty(&samples)
+        .iter()
+        .map(|accuracy| {
+            let color = match accuracy.mean_abs_error {
+                e if e <= 0.2 => Color::Green,
+                e if e <= 0.5 => Color::Yellow,
+                _ => Color::Red,
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("Difficulty {:>2}  ", accuracy.difficulty)),
+                Span::styled(
+                    format!("{:>+5.0}%", accuracy.mean_error * 100.0),
+                    Style::default().fg(color),
+                ),
+                Span::styled(
+                    format!("  ±{:.0}%  {} tasks", accuracy.mean_abs_error * 100.0, accuracy.tasks),
+                    Style::default().fg(Color::Gray),
+                ),
+            ]))
+        })
+        .collect();
+    let list = List::new(items).block(Block::default().borders(Borders::ALL).title(" By Difficulty (avg, ±off) "));
+    f.render_widget(list, chunks[1]);
+}
+
+/// Median and middle half of completion times per difficulty. Levels whose
+/// ranges overlap, or that aren't slower than an easier level, are flagged.
+fn render_calibration(f: &mut Frame, area: Rect, app: &App) {
+    let times = app.db.get_completion_times(app.project_scope()).unwrap_or_default();
+    let levels = models::calibrate_difficulties(&times);
+    let block = Block::default().borders(Borders::ALL).title(" Difficulty Calibration (median, middle half) ");
+
+    if levels.is_empty() {
+        let empty = Paragraph::new("Track time on tasks to see how long each difficulty really takes")
+            .block(block)
+            .style(Style::default().fg(Color::DarkGray))
+            .wrap(Wrap { trim: true });
+        f.render_widget(empty, area);
+        return;
+    }
+
+    let minutes = |seconds: f64| models::format_minutes((seconds / 60.0).round() as i64);
+    let items: Vec<ListItem> = levels
+        .iter()
+        .map(|level| {
+            let mut spans = vec![
+                Span::raw(format!("{:>2} ", level.difficulty)),
+                Span::styled(format!("{:>7}", minutes(level.median_seconds)), Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    format!(" {}–{}", minutes(level.p25_seconds), minutes(level.p75_seconds)),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::styled(format!(" ×{}", level.tasks), Style::default().fg(Color::DarkGray)),
+            ];
+            if let Some(lower) = level.not_slower_than {
+                spans.push(Span::styled(format!(" ⚠ ≤ {}", lower), Style::default().fg(Color::Red)));
+            } else if !level.overlaps_with.is_empty() {
+                let others = level.overlaps_with.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(",");
+                spans.push(Span::styled(format!(" ≈ {}", others), Style::default().fg(Color::Yellow)));
+            } else if !level.is_reliable() {
+                spans.push(Span::styled(" few tasks", Style::default().fg(Color::DarkGray)));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    f.render_widget(List::new(items).block(block), area);
+}
+
+fn render_series_stats(f: &mut Frame, area: Rect, app: &App) {
+    let series = app.db.get_series_stats(app.today()).unwrap_or_default();
+
+    let items: Vec<ListItem> = if series.is_empty() {
+        vec![ListItem::new(Line::from(Span::styled(
+            "Give a task a repeat rule to track it here",
+            Style::default().fg(Color::DarkGray),
+        )))]
+    } else {
+        series
+            .iter()
+            .map(|stat| {
+                let (rate, color) = match stat.completion_rate {
+                    Some(r) if r >= 0.8 => (format!("{:>3.0}%", r * 100.0), Color::Green),
+                    Some(r) if r >= 0.5 => (format!("{:>3.0}%", r * 100.0), Color::Yellow),
+                    Some(r) => (format!("{:>3.0}%", r * 100.0), Color::Red),
+                    None => ("new".to_string(), Color::DarkGray),
+                };
+                let title_style = if stat.active {
+                    Style::default().fg(Color::White)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                ListItem::new(vec![
+                    Line::from(vec![
+                        Span::styled("↻ ", Style::default().fg(Color::Magenta)),
+                        Span::styled(stat.title.clone(), title_style),
+                        Span::styled(format!(" ({})", stat.rule), Style::default().fg(Color::DarkGray)),
+                    ]),
+                    Line::from(vec![
+                        Span::styled(format!("  {:>4}", rate), Style::default().fg(color)),
+                        Span::raw(format!(
+                            "  {}/{} done  ",
+                            stat.instances_completed, stat.occurrences
+                        )),
+                        Span::styled(
+                            format!("{} pts", stat.points_earned),
+                            Style::default().fg(Color::Yellow),
+                        ),
+                    ]),
+                ])
+            })
+            .collect()
+    };
+
+    let list = List::new(items).block(Block::default().borders(Borders::ALL).title(" Recurring "));
+    f.render_widget(list, area);
+}
+
+fn render_tag_stats(f: &mut Frame, area: Rect, app: &App) {
+    let tag_stats = app.db.get_tag_stats().unwrap_or_default();
+
+    let items: Vec<ListItem> = if tag_stats.is_empty() {
+        vec![ListItem::new(Line::from(Span::styled(
+            "Add #tags to tasks to see a breakdown",
+            Style::default().fg(Color::DarkGray),
+        )))]
+    } else {
+        tag_stats
+            .iter()
+            .map(|stat| {
+                ListItem::new(Line::from(vec![
+                    tag_chip(&stat.tag),
+                    Span::styled(
+                        format!(" {} pts", stat.points_earned),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                    Span::raw(format!(
+                        "  {} tasks  {}h {}m",
+                        stat.tasks_completed,
+                        stat.focus_time / 3600,
+                        (stat.focus_time % 3600) / 60
+                    )),
+                ]))
+            })
+            .collect()
+    };
+
+    let list = List::new(items).block(Block::default().borders(Borders::ALL).title(" By Tag "));
+    f.render_widget(list, area);
 }
 
 fn render_stats_overview(f: &mut Frame, area: Rect, app: &App) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .constraints([
+            Constraint::Percentage(35),
+            Constraint::Percentage(30),
+            Constraint::Percentage(35),
+        ])
         .split(area);
 
-    // Left: General stats
-    let hours = app.user_stats.total_focus_time / 3600;
-    let minutes = (app.user_stats.total_focus_time % 3600) / 60;
+    // Left: General stats, narrowed to the current project when one is picked
+    let scope = app.project_scope();
+    let (total_points, tasks_completed, focus_time) = match scope {
+        Some(id) => {
+            let stats = app.db.get_project_stats(Some(id)).unwrap_or_default();
+            (stats.points_earned, stats.tasks_completed, stats.focus_time)
+        }
+        None => (
+            app.user_stats.total_points as i64,
+            app.user_stats.tasks_completed,
+            app.user_stats.total_focus_time,
+        ),
+    };
+    let hours = focus_time / 3600;
+    let minutes = (focus_time % 3600) / 60;
 
     let stats_text = vec![
         Line::from(vec![
             Span::styled("Total Points: ", Style::default().fg(Color::Cyan)),
             Span::styled(
-                app.user_stats.total_points.to_string(),
+                total_points.to_string(),
                 Style::default()
                     .fg(Color::Green)
                     .add_modifier(Modifier::BOLD),
@@ -381,7 +1349,7 @@ fn render_stats_overview(f: &mut Frame, area: Rect, app: &App) {
         ]),
         Line::from(vec![
             Span::styled("Tasks Completed: ", Style::default().fg(Color::Cyan)),
-            Span::raw(app.user_stats.tasks_completed.to_string()),
+            Span::raw(tasks_completed.to_string()),
         ]),
         Line::from(vec![
             Span::styled("Total Focus Time: ", Style::default().fg(Color::Cyan)),
@@ -400,23 +1368,30 @@ fn render_stats_overview(f: &mut Frame, area: Rect, app: &App) {
         ]),
     ];
 
+    let overview_title = match &app.current_project {
+        Some(project) => format!(" Overview: {} (p: project) ", project.name),
+        None => " Overview: all projects (p: project) ".to_string(),
+    };
     let stats_widget = Paragraph::new(stats_text)
-        .block(Block::default().borders(Borders::ALL).title(" Overview "));
+        .block(Block::default().borders(Borders::ALL).title(overview_title));
     f.render_widget(stats_widget, chunks[0]);
 
+    // Middle: Where the points came from
+    render_points_by_reason(f, chunks[1], app);
+
     // Right: Performance scores
     let completed_tasks = app
         .db
-        .get_completed_tasks(50)
+        .get_completed_tasks(50, scope)
         .unwrap_or_default();
-    let avg_times = app.db.get_avg_time_by_difficulty().unwrap_or_default();
+    let avg_times = app.db.get_avg_time_by_difficulty(scope).unwrap_or_default();
 
-    let focus_score = scoring::calculate_focus_score(&completed_tasks);
-    let efficiency_score = scoring::calculate_efficiency_score(&completed_tasks, &avg_times);
+    let focus_score = app.strategy.focus_score(&completed_tasks);
+    let efficiency_score = app.strategy.efficiency_score(&completed_tasks, &avg_times);
 
     let gauge_area = Block::default().borders(Borders::ALL).title(" Performance ");
-    let inner_area = gauge_area.inner(chunks[1]);
-    f.render_widget(gauge_area, chunks[1]);
+    let inner_area = gauge_area.inner(chunks[2]);
+    f.render_widget(gauge_area, chunks[2]);
 
     let inner_chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -438,6 +1413,35 @@ fn render_stats_overview(f: &mut Frame, area: Rect, app: &App) {
     f.render_widget(efficiency_gauge, inner_chunks[1]);
 }
 
+fn render_points_by_reason(f: &mut Frame, area: Rect, app: &App) {
+    let totals = app.db.get_points_by_reason().unwrap_or_default();
+
+    let lines: Vec<Line> = if totals.is_empty() {
+        vec![Line::from(Span::styled(
+            "Complete a task to earn points",
+            Style::default().fg(Color::Gray),
+        ))]
+    } else {
+        totals
+            .iter()
+            .map(|(reason, points)| {
+                let color = if *points < 0 { Color::Red } else { Color::Green };
+                Line::from(vec![
+                    Span::styled(format!("{}: ", reason.as_str()), Style::default().fg(Color::Cyan)),
+                    Span::styled(format!("{:+}", points), Style::default().fg(color)),
+                ])
+            })
+            .collect()
+    };
+
+    let widget = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Points by Source (r: rebuild, R: recompute) "),
+    );
+    f.render_widget(widget, area);
+}
+
 fn render_charts(f: &mut Frame, area: Rect, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
@@ -445,7 +1449,7 @@ fn render_charts(f: &mut Frame, area: Rect, app: &mut App) {
         .split(area);
 
     // Left: Daily points chart
-    if let Ok(daily_stats) = app.db.get_daily_stats(14) {
+    if let Ok(daily_stats) = app.db.get_daily_stats(14, app.clock.now(), &app.day_boundary, app.project_scope()) {
         let data: Vec<(&str, u64)> = daily_stats
             .iter()
             .rev()
@@ -471,7 +1475,7 @@ fn render_charts(f: &mut Frame, area: Rect, app: &mut App) {
     }
 
     // Right: Focus time chart
-    if let Ok(daily_stats) = app.db.get_daily_stats(14) {
+    if let Ok(daily_stats) = app.db.get_daily_stats(14, app.clock.now(), &app.day_boundary, app.project_scope()) {
         let chart_data: Vec<(f64, f64)> = daily_stats
             .iter()
             .rev()
@@ -530,7 +1534,7 @@ fn render_charts(f: &mut Frame, area: Rect, app: &mut App) {
 }
 
 fn render_recent_tasks(f: &mut Frame, area: Rect, app: &App) {
-    if let Ok(recent) = app.db.get_completed_tasks(10) {
+    if let Ok(recent) = app.db.get_completed_tasks(10, app.project_scope()) {
         let items: Vec<ListItem> = recent
             .iter()
             .map(|task| {
@@ -557,17 +1561,43 @@ fn render_recent_tasks(f: &mut Frame, area: Rect, app: &App) {
     }
 }
 
-fn render_help(f: &mut Frame, area: Rect) {
-    let help_text = vec![
+fn render_help(f: &mut Frame, area: Rect, app: &App) {
+    let kind = app.strategy.kind();
+    let rules = app.strategy.rules();
+    let milestones = rules
+        .milestone_thresholds
+        .iter()
+        .map(|t| t.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let config_source = match Config::get_config_path() {
+        Ok(path) if path.exists() => format!("Rules loaded from {}", path.display()),
+        Ok(path) => format!("Default rules; create {} to customize", path.display()),
+        Err(_) => "Default rules".to_string(),
+    };
+
+    let mut help_text = vec![
         Line::from(vec![
             Span::styled("Task Management", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
         ]),
         Line::from("  a         - Add new task"),
+        Line::from("  A         - Add subtask under selected task"),
+        Line::from("  ←/h →/l   - Collapse/expand subtasks"),
         Line::from("  e         - Edit selected task"),
+        Line::from("  n         - Edit notes of selected task (Ctrl-s saves)"),
         Line::from("  d         - Delete selected task"),
         Line::from("  Space     - Start/Pause task"),
         Line::from("  x         - Complete task (mark as done)"),
         Line::from("  c         - Toggle completed tasks view"),
+        Line::from("  t         - Filter tasks by tag"),
+        Line::from("  f         - Fuzzy filter by title (Enter keeps it, Esc shows all)"),
+        Line::from("  /         - Search titles and notes, completed tasks too"),
+        Line::from("  p         - Switch project (m in the picker moves the selected task)"),
+        Line::from("  o         - Sort by created, due, difficulty, status, points, time or title"),
+        Line::from("  O         - Reverse the sort order"),
+        Line::from("  b         - Then by: second sort key for ties"),
+        Line::from("  u         - Undo last task change"),
+        Line::from("  Ctrl-r    - Redo"),
         Line::from(""),
         Line::from(vec![
             Span::styled("Navigation", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
@@ -577,19 +1607,79 @@ fn render_help(f: &mut Frame, area: Rect) {
         Line::from("  1         - Task List view"),
         Line::from("  2         - Stats view"),
         Line::from("  3         - Help view"),
-        Line::from("  q/Esc     - Quit"),
+        Line::from("  r         - Rebuild stats from points ledger (Stats view)"),
+        Line::from("  R         - Recompute all points from task history (Stats view)"),
+        Line::from("  q/Esc     - Quit (Esc clears a fuzzy filter first)"),
         Line::from(""),
         Line::from(vec![
             Span::styled("Scoring System", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
         ]),
-        Line::from("  • Base points = Difficulty × 10"),
-        Line::from("  • Time bonus for fast completion"),
-        Line::from("  • Streak multiplier (up to 50% bonus)"),
-        Line::from("  • Focus bonus for fewer interruptions"),
-        Line::from("  • Milestone rewards at 10, 25, 50, 100+ tasks"),
-        Line::from("  • Gradual streak decay (not instant reset!)"),
     ];
 
+    help_text.push(Line::from(vec![
+        Span::styled("  Strategy: ", Style::default().fg(Color::Cyan)),
+        Span::styled(kind.as_str().to_string(), Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(format!(
+            " (profile '{}') - {}",
+            app.config.active_profile_name(),
+            kind.description()
+        )),
+    ]));
+    help_text.push(Line::from(format!(
+        "  • Base points = Difficulty × {}",
+        rules.base_points_multiplier
+    )));
+
+    let streak_line = Line::from(format!(
+        "  • Streak multiplier (+{:.0}% per day, up to {:.0}% bonus)",
+        rules.streak_step * 100.0,
+        rules.streak_cap * 100.0
+    ));
+    let milestone_line = Line::from(format!(
+        "  • Milestone rewards ({} base) at {} tasks",
+        rules.milestone_bonus, milestones
+    ));
+    let decay_line = Line::from(format!(
+        "  • Gradual streak decay: bonus pool ×{} after 2-3 idle days, ×{} after 4+",
+        rules.short_gap_decay, rules.long_gap_decay
+    ));
+
+    match kind {
+        StrategyKind::Classic => {
+            help_text.push(Line::from("  • Time bonus for fast completion"));
+            help_text.push(streak_line);
+            help_text.push(Line::from("  • Focus bonus for fewer interruptions"));
+            help_text.push(milestone_line);
+            help_text.push(decay_line);
+        }
+        StrategyKind::DifficultyOnly => {
+            help_text.push(Line::from("  • No bonuses, multipliers or milestones"));
+        }
+        StrategyKind::EstimateAccuracy => {
+            help_text.push(Line::from(
+                "  • Accuracy bonus (up to 50%) for finishing close to the task's estimate, or the usual time for its difficulty when it has none",
+            ));
+            help_text.push(streak_line);
+            help_text.push(milestone_line);
+            help_text.push(decay_line);
+        }
+    }
+
+    if kind != StrategyKind::DifficultyOnly && rules.on_time_bonus > 0.0 {
+        help_text.push(Line::from(format!(
+            "  • On-time bonus: +{:.0}% of base for finishing by the due date",
+            rules.on_time_bonus * 100.0
+        )));
+    }
+    if kind != StrategyKind::DifficultyOnly {
+        help_text.push(Line::from(format!("  • Streak days: {}", app.day_boundary.describe())));
+    }
+
+    help_text.push(Line::from(Span::styled(
+        format!("  {}", config_source),
+        Style::default().fg(Color::DarkGray),
+    )));
+
     let help = Paragraph::new(help_text)
         .block(
             Block::default()
@@ -610,6 +1700,24 @@ fn render_footer(f: &mut Frame, area: Rect, app: &App) {
             InputMode::AddingTask | InputMode::EditingTask => {
                 "Tab: Switch field | Enter: Submit | Esc: Cancel".to_string()
             }
+            InputMode::ConfirmRecompute => "y: Apply recompute | n: Keep existing points".to_string(),
+            InputMode::FilteringTags => "Space: Toggle tag | c: Clear | Enter/Esc: Close".to_string(),
+            InputMode::PickingProject => {
+                "Enter: Switch project | m: Move selected task here | Esc: Close".to_string()
+            }
+            InputMode::NamingProject => "Enter: Create project | Esc: Cancel".to_string(),
+            InputMode::Searching => "Enter: Go to task | ↑/↓: Select | Ctrl-u: Clear | Esc: Close".to_string(),
+            InputMode::FuzzyFiltering => {
+                "Type to filter | ↑/↓: Select | Enter: Keep filter | Esc: Show all".to_string()
+            }
+            InputMode::EditingNotes => {
+                let (row, col) = app.notes_editor.cursor();
+                format!(
+                    "Ctrl-s: Save | Esc: Cancel | Ctrl-←/→: Word | Ctrl-w: Delete word | Ln {}, Col {}",
+                    row + 1,
+                    col + 1
+                )
+            }
         }
     };
 
@@ -0,0 +1,153 @@
+use crate::models::{LedgerEntry, Task, UserStats};
+
+const MAX_UNDO_DEPTH: usize = 50;
+
+/// Everything needed to put a task, and the points and stats it affected,
+/// back exactly as they were at one point in time
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub task: Option<Task>, // None when the task did not exist
+    pub ledger: Vec<LedgerEntry>,
+    pub user_stats: UserStats,
+}
+
+#[derive(Debug, Clone)]
+pub struct UndoEntry {
+    pub description: String,
+    pub task_id: i64,
+    pub before: Snapshot,
+    pub after: Snapshot,
+    pub linked: Vec<UndoEntry>, // other tasks the same operation changed
+}
+
+/// Bounded undo/redo history. Recording a new operation discards anything
+/// that could previously have been redone.
+#[derive(Debug, Clone, Default)]
+pub struct UndoStack {
+    undo: Vec<UndoEntry>,
+    redo: Vec<UndoEntry>,
+}
+
+impl UndoStack {
+    pub fn record(&mut self, entry: UndoEntry) {
+        self.redo.clear();
+        self.undo.push(entry);
+        if self.undo.len() > MAX_UNDO_DEPTH {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Make another task's change part of the most recent operation, so
+    /// both are undone and redone together
+    pub fn link_to_last(&mut self, entry: UndoEntry) {
+        if let Some(last) = self.undo.last_mut() {
+            last.linked.push(entry);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    pub fn take_undo(&mut self) -> Option<UndoEntry> {
+        self.undo.pop()
+    }
+
+    pub fn take_redo(&mut self) -> Option<UndoEntry> {
+        self.redo.pop()
+    }
+
+    pub fn push_undone(&mut self, entry: UndoEntry) {
+        self.redo.push(entry);
+    }
+
+    pub fn push_redone(&mut self, entry: UndoEntry) {
+        self.undo.push(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(task_id: i64) -> UndoEntry {
+        let snapshot = Snapshot {
+            task: None,
+            ledger: Vec::new(),
+            user_stats: UserStats::default(),
+        };
+        UndoEntry {
+            description: format!("edit #{}", task_id),
+            task_id,
+            before: snapshot.clone(),
+            after: snapshot,
+            linked: Vec::new(),
+        }
+    }
+
+    fn ids(entry: Option<UndoEntry>) -> Option<i64> {
+        entry.map(|e| e.task_id)
+    }
+
+    #[test]
+    fn undo_and_redo_move_entries_between_the_stacks() {
+        let mut stack = UndoStack::default();
+        stack.record(entry(1));
+        stack.record(entry(2));
+
+        let undone = stack.take_undo().unwrap();
+        assert_eq!(undone.task_id, 2);
+        stack.push_undone(undone);
+
+        let redone = stack.take_redo().unwrap();
+        assert_eq!(redone.task_id, 2);
+        stack.push_redone(redone);
+        assert_eq!(ids(stack.take_redo()), None);
+        assert_eq!(ids(stack.take_undo()), Some(2));
+        assert_eq!(ids(stack.take_undo()), Some(1));
+        assert_eq!(ids(stack.take_undo()), None);
+    }
+
+    #[test]
+    fn recording_discards_the_redo_history() {
+        let mut stack = UndoStack::default();
+        stack.record(entry(1));
+        let undone = stack.take_undo().unwrap();
+        stack.push_undone(undone);
+
+        stack.record(entry(2));
+        assert_eq!(ids(stack.take_redo()), None);
+        assert_eq!(ids(stack.take_undo()), Some(2));
+    }
+
+    #[test]
+    fn linked_entries_join_the_last_operation() {
+        let mut stack = UndoStack::default();
+        stack.link_to_last(entry(9)); // nothing to join yet
+        stack.record(entry(1));
+        stack.link_to_last(entry(2));
+        stack.link_to_last(entry(3));
+
+        let undone = stack.take_undo().unwrap();
+        let linked: Vec<i64> = undone.linked.iter().map(|e| e.task_id).collect();
+        assert_eq!((undone.task_id, linked), (1, vec![2, 3]));
+        assert_eq!(ids(stack.take_undo()), None);
+    }
+
+    #[test]
+    fn only_the_latest_operations_are_kept() {
+        let mut stack = UndoStack::default();
+        for task_id in 1..=MAX_UNDO_DEPTH as i64 + 5 {
+            stack.record(entry(task_id));
+        }
+
+        let mut remaining = Vec::new();
+        while let Some(entry) = stack.take_undo() {
+            remaining.push(entry.task_id);
+        }
+        assert_eq!(remaining.len(), MAX_UNDO_DEPTH);
+        assert_eq!(remaining.first(), Some(&(MAX_UNDO_DEPTH as i64 + 5)));
+        assert_eq!(remaining.last(), Some(&6));
+    }
+}